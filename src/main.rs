@@ -1,13 +1,101 @@
 extern crate sdl2;
 extern crate sdl2_image;
 extern crate sdl2_ttf;
+extern crate sdl2_mixer;
 extern crate rand;
+extern crate rustc_serialize;
 
 mod phi;
 mod views;
 
+use phi::Error;
+use phi::input::Bindings;
+use phi::window::WindowConfig;
+
+/// Where the player's key bindings are kept
+const BINDINGS_PATH: &'static str = "bindings.cfg";
+
+/// What the player asked for on the command line.
+struct Options {
+    /// Where the replay of this run should be saved
+    record: Option<String>,
+
+    /// The replay which should be played instead of reading the input
+    replay: Option<String>,
+
+    /// The seed of the random number generator, if it shouldn't be random
+    seed: Option<u64>,
+}
+
+impl Options {
+    fn parse() -> Result<Options, String> {
+        let mut options = Options { record: None, replay: None, seed: None };
+        let mut args = ::std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match &arg[..] {
+                "--record" => options.record = Some(args.next().ok_or("--record needs a path")?),
+                "--replay" => options.replay = Some(args.next().ok_or("--replay needs a path")?),
+                "--seed" => {
+                    let seed = args.next().ok_or("--seed needs a number")?;
+                    options.seed = Some(seed.parse().map_err(|_| format!("invalid seed `{}`", seed))?);
+                },
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
 fn main() {
-    ::phi::spawn("ArcadeRS Shooter", |phi| {
-        Box::new(::views::main_menu::MainMenuView::new(phi))
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("Usage: arcade-rs [--seed <number>] [--record <path>] [--replay <path>]\n{}", msg);
+            ::std::process::exit(2);
+        }
+    };
+
+    let config = WindowConfig::new("ArcadeRS Shooter")
+        .size(800, 600)
+        .min_size(400, 300)
+        .vsync(true);
+
+    let result = ::phi::spawn(config, |phi| {
+        // On the first run, we save the default bindings so that the player
+        // can edit them.
+        match Bindings::load(BINDINGS_PATH) {
+            Ok(bindings) => phi.bindings = bindings,
+            Err(Error::AssetNotFound(_)) => phi.bindings.save(BINDINGS_PATH)?,
+            Err(error) => return Err(error),
+        }
+
+        if let Some(seed) = options.seed {
+            phi.set_seed(seed);
+        }
+
+        // The replay must be loaded after the seed is set, because it
+        // restores its own, and before recording, which saves it.
+        if let Some(ref path) = options.replay {
+            phi.start_replay(path)?;
+        }
+
+        if let Some(ref path) = options.record {
+            phi.start_recording(path)?;
+        }
+
+        // Printed when the run is meant to be reproduced, through `--seed`
+        // or a replay.
+        if options.seed.is_some() || options.record.is_some() {
+            println!("Seed: {}", phi.seed());
+        }
+
+        Ok(Box::new(::views::main_menu::MainMenuView::new(phi)?))
     });
+
+    if let Err(error) = result {
+        eprintln!("Error: {}", error);
+        ::std::process::exit(1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,74 @@
+use phi::Error;
+use phi::gfx::Sprite;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+use sdl2::render::{Renderer, Texture};
+
+/// Hands out sprites loaded from image files, so that every file is only
+/// decoded and uploaded once, however many views use it.
+///
+/// The manager doesn't keep the textures alive by itself: they are reference
+/// counted by the sprites using them, and freed once the last one is dropped.
+/// Loading the same file after that decodes it again.
+pub struct AssetManager {
+    textures: HashMap<PathBuf, Weak<RefCell<Texture>>>,
+}
+
+impl AssetManager {
+    pub fn new() -> AssetManager {
+        AssetManager {
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Returns a sprite showing the whole image located at `path`. If a
+    /// sprite of this image is still alive, its texture is reused.
+    pub fn sprite(&mut self, renderer: &Renderer, path: &str) -> Result<Sprite, Error> {
+        let key = PathBuf::from(path);
+
+        if let Some(tex) = self.textures.get(&key).and_then(Weak::upgrade) {
+            return Ok(Sprite::from_shared(tex));
+        }
+
+        // Either the image was never loaded, or nothing uses it anymore. We
+        // take this opportunity to forget about the other freed textures.
+        self.collect();
+
+        let sprite = Sprite::load(renderer, path)?;
+        self.textures.insert(key, Rc::downgrade(sprite.texture()));
+        Ok(sprite)
+    }
+
+    /// The number of textures which are currently alive.
+    pub fn len(&self) -> usize {
+        self.live_textures().count()
+    }
+
+    /// Whether no texture loaded through the manager is alive.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The amount of memory, in bytes, used by the textures which are alive.
+    pub fn texture_memory(&self) -> usize {
+        self.live_textures()
+            .map(|tex| {
+                let query = tex.borrow().query();
+                query.width as usize * query.height as usize
+                    * query.format.byte_size_per_pixel()
+            })
+            .sum()
+    }
+
+    /// Iterates over the textures which haven't been freed yet.
+    fn live_textures<'a>(&'a self) -> Box<Iterator<Item=Rc<RefCell<Texture>>> + 'a> {
+        Box::new(self.textures.values().filter_map(Weak::upgrade))
+    }
+
+    /// Removes the entries of the textures which were freed.
+    fn collect(&mut self) {
+        self.textures.retain(|_, tex| tex.upgrade().is_some());
+    }
+}
@@ -0,0 +1,188 @@
+use phi::{Error, Phi};
+use phi::data::Rectangle;
+use phi::gfx::Sprite;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use rustc_serialize::json::{Json, Object};
+
+/// Describes the named regions of a spritesheet, along with the sequences of
+/// regions which make up its animations, so that the art can change without
+/// recompiling the game.
+///
+/// Atlases are read from descriptors in the JSON-hash format exported by
+/// TexturePacker, exported without rotating or trimming the frames. Animations
+/// are listed in its `animations` object, each as the names of its frames in
+/// order:
+///
+/// ```json
+/// {
+///     "frames": {
+///         "idle": { "frame": { "x": 0, "y": 0, "w": 43, "h": 39 } },
+///         "boost": { "frame": { "x": 43, "y": 0, "w": 43, "h": 39 } }
+///     },
+///     "animations": {
+///         "flying": ["idle", "boost"]
+///     },
+///     "meta": { "image": "spaceship.png" }
+/// }
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// let atlas = Atlas::load("assets/spaceship.json")?;
+/// let idle = atlas.sprite(phi, "idle")?;
+/// let flying = AnimatedSprite::with_fps(atlas.sprites(phi, "flying")?, 10.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    /// The path of the descriptor, to report errors
+    path: PathBuf,
+
+    /// The path of the spritesheet, relative to the working directory
+    image: String,
+
+    frames: HashMap<String, Rectangle>,
+    animations: HashMap<String, Vec<String>>,
+}
+
+impl Atlas {
+    /// Reads the descriptor located at `path`. The spritesheet itself is only
+    /// loaded when sprites are requested.
+    pub fn load(path: &str) -> Result<Atlas, Error> {
+        let path = Path::new(path);
+        let mut text = String::new();
+
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => Error::AssetNotFound(path.to_path_buf()),
+                _ => Error::Io(path.to_path_buf(), e),
+            })?;
+
+        Atlas::parse(path, &text).map_err(|msg| Error::Decode(path.to_path_buf(), msg))
+    }
+
+    fn parse(path: &Path, text: &str) -> Result<Atlas, String> {
+        let json = Json::from_str(text).map_err(|e| e.to_string())?;
+        let root = json.as_object().ok_or("expected an object")?;
+
+        // The image is found next to the descriptor.
+        let image = field(root, "meta")?.as_object()
+            .and_then(|meta| meta.get("image"))
+            .and_then(Json::as_string)
+            .ok_or("expected the name of the image in meta.image")?;
+
+        let image = path.parent().unwrap_or(Path::new(""))
+            .join(image)
+            .to_string_lossy()
+            .into_owned();
+
+        let mut frames = HashMap::new();
+
+        for (name, frame) in field(root, "frames")?.as_object().ok_or("expected frames to be an object")? {
+            let frame = frame.as_object().ok_or_else(|| format!("expected frame {:?} to be an object", name))?;
+
+            if frame.get("rotated").and_then(Json::as_boolean).unwrap_or(false) {
+                return Err(format!("frame {:?} is rotated, which isn't supported", name));
+            }
+
+            // A trimmed frame would be drawn shifted and stretched, as its
+            // offset inside of the original image is ignored.
+            if frame.get("trimmed").and_then(Json::as_boolean).unwrap_or(false) {
+                return Err(format!("frame {:?} is trimmed, which isn't supported", name));
+            }
+
+            let rect = to_rectangle(field(frame, "frame")?)
+                .ok_or_else(|| format!("expected the x, y, w and h of frame {:?}", name))?;
+
+            frames.insert(name.clone(), rect);
+        }
+
+        let mut animations = HashMap::new();
+
+        // Animations are optional, as not every exporter writes them.
+        if let Some(list) = root.get("animations") {
+            for (name, sequence) in list.as_object().ok_or("expected animations to be an object")? {
+                let names = sequence.as_array()
+                    .and_then(|names| names.iter()
+                        .map(|n| n.as_string().map(str::to_string))
+                        .collect::<Option<Vec<String>>>())
+                    .ok_or_else(|| format!("expected animation {:?} to be a list of names", name))?;
+
+                if names.is_empty() {
+                    return Err(format!("animation {:?} has no frames", name));
+                }
+
+                if let Some(missing) = names.iter().find(|n| !frames.contains_key(*n)) {
+                    return Err(format!("animation {:?} uses the unknown frame {:?}", name, missing));
+                }
+
+                animations.insert(name.clone(), names);
+            }
+        }
+
+        Ok(Atlas {
+            path: path.to_path_buf(),
+            image: image,
+            frames: frames,
+            animations: animations,
+        })
+    }
+
+    /// The path of the spritesheet which the regions are taken from.
+    pub fn image(&self) -> &str {
+        &self.image
+    }
+
+    /// Returns the region of the spritesheet named `name`.
+    pub fn region(&self, name: &str) -> Result<Rectangle, Error> {
+        self.frames.get(name).cloned()
+            .ok_or_else(|| Error::Decode(self.path.clone(), format!("no frame named {:?}", name)))
+    }
+
+    /// Returns the regions making up the animation named `name`, in order.
+    pub fn regions(&self, name: &str) -> Result<Vec<Rectangle>, Error> {
+        let names = self.animations.get(name)
+            .ok_or_else(|| Error::Decode(self.path.clone(), format!("no animation named {:?}", name)))?;
+
+        names.iter().map(|frame| self.region(frame)).collect()
+    }
+
+    /// Returns a sprite showing the region named `name`.
+    pub fn sprite(&self, phi: &mut Phi, name: &str) -> Result<Sprite, Error> {
+        phi.load_sprite(&self.image)?.region(self.region(name)?)
+    }
+
+    /// Returns a sprite for every frame of the animation named `name`, which
+    /// all share the texture of the spritesheet.
+    pub fn sprites(&self, phi: &mut Phi, name: &str) -> Result<Vec<Sprite>, Error> {
+        let sheet = phi.load_sprite(&self.image)?;
+
+        self.regions(name)?.into_iter()
+            .map(|region| sheet.region(region))
+            .collect()
+    }
+}
+
+/// Returns the field of `object` named `name`, which must be present.
+fn field<'a>(object: &'a Object, name: &str) -> Result<&'a Json, String> {
+    object.get(name).ok_or_else(|| format!("missing field {:?}", name))
+}
+
+/// Reads a rectangle written as `{ "x": 0, "y": 0, "w": 43, "h": 39 }`.
+fn to_rectangle(json: &Json) -> Option<Rectangle> {
+    let rect = match json.as_object() {
+        Some(rect) => rect,
+        None => return None,
+    };
+
+    let number = |name: &str| rect.get(name).and_then(Json::as_f64);
+
+    match (number("x"), number("y"), number("w"), number("h")) {
+        (Some(x), Some(y), Some(w), Some(h)) => Some(Rectangle { x: x, y: y, w: w, h: h }),
+        _ => None,
+    }
+}
@@ -0,0 +1,252 @@
+use phi::Error;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use sdl2::{AudioSubsystem, Sdl};
+use sdl2_mixer::{self, Channel, Chunk, Music, MAX_VOLUME};
+
+/// The number of sound effects which can play at the same time, unless told
+/// otherwise through `Audio::set_channels`.
+const DEFAULT_CHANNELS: i32 = 16;
+
+/// Plays streamed music and short sound effects through SDL_mixer.
+///
+/// Volumes go from 0 to 1. The volume of the music and of the sound effects
+/// are both scaled by the master volume.
+///
+/// If no audio device could be opened, the game goes on without sound: every
+/// method then succeeds without doing anything.
+pub struct Audio {
+    enabled: bool,
+
+    /// SDL's audio subsystem, which must stay initialized while the device
+    /// is open
+    _subsystem: Option<AudioSubsystem>,
+
+    /// The music which is currently playing, kept alive while it streams
+    music: Option<Music>,
+
+    /// Sound effects are small and played often, so they are decoded once
+    sounds: HashMap<PathBuf, Chunk>,
+
+    master_volume: f64,
+    music_volume: f64,
+    sfx_volume: f64,
+}
+
+impl Audio {
+    /// Initializes SDL's audio subsystem and opens the default audio device.
+    /// Sound effects are WAV files, which SDL_mixer decodes without any of
+    /// its optional libraries.
+    pub fn open(sdl_context: &Sdl) -> Result<Audio, Error> {
+        let subsystem = sdl_context.audio()?;
+
+        sdl2_mixer::open_audio(
+            sdl2_mixer::DEFAULT_FREQUENCY,
+            sdl2_mixer::AUDIO_S16LSB,
+            sdl2_mixer::DEFAULT_CHANNELS,
+            1_024,
+        )?;
+
+        sdl2_mixer::allocate_channels(DEFAULT_CHANNELS);
+
+        let mut audio = Audio::disabled();
+        audio.enabled = true;
+        audio._subsystem = Some(subsystem);
+        audio.apply_volumes();
+        Ok(audio)
+    }
+
+    /// Creates a silent mixer, for when no audio device is available.
+    pub fn disabled() -> Audio {
+        Audio {
+            enabled: false,
+            _subsystem: None,
+            music: None,
+            sounds: HashMap::new(),
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Starts streaming the music located at `path`, replacing the current
+    /// one. If `looping`, it restarts whenever it ends.
+    pub fn play_music(&mut self, path: &str, looping: bool) -> Result<(), Error> {
+        self.fade_in_music(path, looping, 0.0)
+    }
+
+    /// Same as `play_music`, but the music fades in during `seconds`.
+    pub fn fade_in_music(&mut self, path: &str, looping: bool, seconds: f64) -> Result<(), Error> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let music = Music::from_file(&asset_path(path)?)
+            .map_err(|e| Error::Decode(PathBuf::from(path), e.to_string()))?;
+
+        let loops = if looping { -1 } else { 1 };
+
+        if seconds > 0.0 {
+            music.fade_in(loops, (seconds * 1_000.0) as i32)?;
+        } else {
+            music.play(loops)?;
+        }
+
+        self.music = Some(music);
+        Ok(())
+    }
+
+    /// Stops the music at once.
+    pub fn stop_music(&mut self) {
+        if self.enabled {
+            Music::halt();
+        }
+
+        self.music = None;
+    }
+
+    /// Fades the music out during `seconds`, after which it stops. The music
+    /// is kept alive until then, or until another one starts.
+    pub fn fade_out_music(&mut self, seconds: f64) -> Result<(), Error> {
+        if !self.enabled || self.music.is_none() {
+            return Ok(());
+        }
+
+        Ok(Music::fade_out((seconds * 1_000.0) as i32)?)
+    }
+
+    pub fn is_music_playing(&self) -> bool {
+        self.enabled && Music::is_playing()
+    }
+
+    /// Decodes the sound effect located at `path` ahead of time, so that
+    /// playing it for the first time doesn't cause a hiccup.
+    pub fn load_sound(&mut self, path: &str) -> Result<(), Error> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let key = PathBuf::from(path);
+
+        if !self.sounds.contains_key(&key) {
+            let mut chunk = Chunk::from_file(&asset_path(path)?)
+                .map_err(|e| Error::Decode(key.clone(), e.to_string()))?;
+
+            chunk.set_volume(self.volume(self.sfx_volume));
+            self.sounds.insert(key, chunk);
+        }
+
+        Ok(())
+    }
+
+    /// Plays the sound effect located at `path` once, on the first free
+    /// channel. Returns the channel, or `None` if they are all busy, in which
+    /// case the sound is skipped.
+    pub fn play_sound(&mut self, path: &str) -> Result<Option<i32>, Error> {
+        if !self.enabled {
+            return Ok(None);
+        }
+
+        self.load_sound(path)?;
+
+        // Every channel being busy isn't worth reporting.
+        let chunk = &self.sounds[Path::new(path)];
+        Ok(Channel::all().play(chunk, 0).ok().map(|Channel(i)| i))
+    }
+
+    /// Stops the sound effect playing on `channel`.
+    pub fn stop_channel(&mut self, channel: i32) {
+        if self.enabled {
+            Channel(channel).halt();
+        }
+    }
+
+    /// Stops every sound effect.
+    pub fn stop_sounds(&mut self) {
+        if self.enabled {
+            Channel::all().halt();
+        }
+    }
+
+    /// Sets the number of sound effects which can play at the same time.
+    pub fn set_channels(&mut self, channels: i32) {
+        if self.enabled {
+            sdl2_mixer::allocate_channels(channels);
+            self.apply_volumes();
+        }
+    }
+
+    pub fn set_master_volume(&mut self, volume: f64) {
+        self.master_volume = volume.max(0.0).min(1.0);
+        self.apply_volumes();
+    }
+
+    pub fn set_music_volume(&mut self, volume: f64) {
+        self.music_volume = volume.max(0.0).min(1.0);
+        self.apply_volumes();
+    }
+
+    pub fn set_sfx_volume(&mut self, volume: f64) {
+        self.sfx_volume = volume.max(0.0).min(1.0);
+        self.apply_volumes();
+    }
+
+    pub fn master_volume(&self) -> f64 {
+        self.master_volume
+    }
+
+    pub fn music_volume(&self) -> f64 {
+        self.music_volume
+    }
+
+    pub fn sfx_volume(&self) -> f64 {
+        self.sfx_volume
+    }
+
+    /// Converts a volume, scaled by the master volume, to SDL_mixer's range.
+    fn volume(&self, volume: f64) -> i32 {
+        (self.master_volume * volume * MAX_VOLUME as f64).round() as i32
+    }
+
+    /// Tells SDL_mixer about the current volumes.
+    fn apply_volumes(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        Music::set_volume(self.volume(self.music_volume));
+
+        let sfx_volume = self.volume(self.sfx_volume);
+        for chunk in self.sounds.values_mut() {
+            chunk.set_volume(sfx_volume);
+        }
+    }
+}
+
+impl Drop for Audio {
+    fn drop(&mut self) {
+        if self.enabled {
+            // Everything must be freed before the device is closed.
+            self.stop_music();
+            self.stop_sounds();
+            self.sounds.clear();
+            sdl2_mixer::close_audio();
+        }
+    }
+}
+
+/// Checks that an asset exists before handing it to SDL_mixer, whose errors
+/// don't tell a missing file apart from a corrupted one.
+fn asset_path(path: &str) -> Result<&Path, Error> {
+    let path = Path::new(path);
+
+    if path.exists() {
+        Ok(path)
+    } else {
+        Err(Error::AssetNotFound(path.to_path_buf()))
+    }
+}
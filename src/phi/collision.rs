@@ -0,0 +1,188 @@
+use phi::data::Rectangle;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+
+/// The side of the cells of the spatial hash, in pixels, unless told
+/// otherwise through `Collisions::with_cell_size`. It should be somewhat larger
+/// than most bodies.
+const DEFAULT_CELL_SIZE: f64 = 128.0;
+
+/// What happened to a pair of bodies since the last call to
+/// `Collisions::detect`. The identifiers of the bodies are always in
+/// increasing order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionEvent<T> {
+    /// The bodies started overlapping.
+    Enter(T, T),
+
+    /// The bodies were already overlapping, and still are.
+    Stay(T, T),
+
+    /// The bodies stopped overlapping, or one of them was removed.
+    Exit(T, T),
+}
+
+/// A rectangle taking part in collisions, identified by `id`.
+///
+/// Every body sits on some layers, and is only interested in the layers set
+/// in its mask: two bodies collide if either of them is interested in one of
+/// the layers of the other.
+#[derive(Clone, Copy, Debug)]
+pub struct Body<T> {
+    pub id: T,
+    pub rect: Rectangle,
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl<T> Body<T> {
+    fn interacts_with(&self, other: &Body<T>) -> bool {
+        self.mask & other.layer != 0 || other.mask & self.layer != 0
+    }
+}
+
+/// Finds the bodies which overlap, and tells how that changed since the last
+/// frame.
+///
+/// Rather than checking every pair of bodies, they are sorted into the cells
+/// of a grid, and only the bodies which share a cell are compared.
+///
+/// # Examples
+///
+/// ```
+/// let mut collisions = Collisions::new();
+///
+/// // On every frame
+/// collisions.clear();
+/// collisions.insert(Body { id: ship, rect: ship_rect, layer: PLAYER, mask: ENEMY });
+/// collisions.insert(Body { id: asteroid, rect: asteroid_rect, layer: ENEMY, mask: 0 });
+///
+/// for event in collisions.detect() {
+///     if let CollisionEvent::Enter(a, b) = event {
+///         println!("{:?} hit {:?}", a, b);
+///     }
+/// }
+/// ```
+pub struct Collisions<T> {
+    cell_size: f64,
+    bodies: Vec<Body<T>>,
+
+    /// The indices, in `bodies`, of the bodies touching every cell
+    cells: HashMap<(i32, i32), Vec<usize>>,
+
+    /// The pairs which overlapped during the last call to `detect`
+    overlapping: BTreeSet<(T, T)>,
+}
+
+impl<T: Copy + Eq + Hash + Ord> Collisions<T> {
+    pub fn new() -> Collisions<T> {
+        Collisions::with_cell_size(DEFAULT_CELL_SIZE)
+    }
+
+    pub fn with_cell_size(cell_size: f64) -> Collisions<T> {
+        Collisions {
+            cell_size: cell_size,
+            bodies: Vec::new(),
+            cells: HashMap::new(),
+            overlapping: BTreeSet::new(),
+        }
+    }
+
+    /// Removes every body, while remembering which pairs were overlapping, so
+    /// that the bodies can be inserted again at their new position.
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, body: Body<T>) {
+        let index = self.bodies.len();
+
+        for cell in self.cells_of(body.rect) {
+            self.cells.entry(cell).or_insert_with(Vec::new).push(index);
+        }
+
+        self.bodies.push(body);
+    }
+
+    /// Returns the identifiers of the bodies which overlap `rect`.
+    pub fn query(&self, rect: Rectangle) -> Vec<T> {
+        let mut found = BTreeSet::new();
+
+        for cell in self.cells_of(rect) {
+            for &i in self.cells.get(&cell).map(|v| &v[..]).unwrap_or(&[]) {
+                if self.bodies[i].rect.overlaps(rect) {
+                    found.insert(self.bodies[i].id);
+                }
+            }
+        }
+
+        found.into_iter().collect()
+    }
+
+    /// Finds the pairs of bodies which overlap, and compares them to the ones
+    /// found by the previous call. Events are sorted by the identifiers of
+    /// their bodies, so that they don't depend on the layout of the grid.
+    pub fn detect(&mut self) -> Vec<CollisionEvent<T>> {
+        self.detect_with(|_, _| true)
+    }
+
+    /// Same as `detect`, but the pairs whose rectangles overlap are only kept
+    /// if `narrow` agrees, for example after comparing their pixels.
+    pub fn detect_with<F>(&mut self, mut narrow: F) -> Vec<CollisionEvent<T>>
+        where F: FnMut(&Body<T>, &Body<T>) -> bool {
+
+        let mut overlapping = BTreeSet::new();
+
+        // Two bodies can share several cells, in which case we don't need to
+        // check them again, whatever the outcome was.
+        let mut checked = HashSet::new();
+
+        for indices in self.cells.values() {
+            for (n, &i) in indices.iter().enumerate() {
+                for &j in &indices[n + 1..] {
+                    if !checked.insert((i.min(j), i.max(j))) {
+                        continue;
+                    }
+
+                    let (a, b) = (&self.bodies[i], &self.bodies[j]);
+
+                    if a.id != b.id && a.interacts_with(b) && a.rect.overlaps(b.rect) && narrow(a, b) {
+                        let pair = if a.id < b.id { (a.id, b.id) } else { (b.id, a.id) };
+                        overlapping.insert(pair);
+                    }
+                }
+            }
+        }
+
+        let mut events: Vec<CollisionEvent<T>> = overlapping.iter()
+            .map(|&(a, b)| {
+                if self.overlapping.contains(&(a, b)) { CollisionEvent::Stay(a, b) }
+                else { CollisionEvent::Enter(a, b) }
+            })
+            .collect();
+
+        events.extend(self.overlapping.difference(&overlapping)
+            .map(|&(a, b)| CollisionEvent::Exit(a, b)));
+
+        self.overlapping = overlapping;
+        events
+    }
+
+    /// The coordinates of the cells which `rect` touches.
+    fn cells_of(&self, rect: Rectangle) -> Vec<(i32, i32)> {
+        let x0 = (rect.x / self.cell_size).floor() as i32;
+        let y0 = (rect.y / self.cell_size).floor() as i32;
+        let x1 = ((rect.x + rect.w) / self.cell_size).floor() as i32;
+        let y1 = ((rect.y + rect.h) / self.cell_size).floor() as i32;
+
+        let mut cells = Vec::new();
+        for x in x0..x1 + 1 {
+            for y in y0..y1 + 1 {
+                cells.push((x, y));
+            }
+        }
+
+        cells
+    }
+}
@@ -1,5 +1,132 @@
+use phi::Error;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
 use sdl2::rect::Rect as SdlRect;
 
+/// A point, or a displacement, in two dimensions.
+///
+/// # Examples
+///
+/// ```
+/// let vel = Vec2::new(3.0, 4.0);
+/// assert_eq!(vel.length(), 5.0);
+/// assert_eq!(vel.normalize() * 10.0, Vec2::new(6.0, 8.0));
+/// assert_eq!(vel + Vec2::new(1.0, 1.0), Vec2::new(4.0, 5.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vec2 {
+    pub fn new(x: f64, y: f64) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+
+    pub fn zero() -> Vec2 {
+        Vec2::new(0.0, 0.0)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    /// Cheaper than `length`, and enough to compare distances.
+    pub fn length_squared(&self) -> f64 {
+        self.dot(*self)
+    }
+
+    /// Returns the vector pointing in the same direction with a length of 1,
+    /// or the zero vector if `self` has no direction.
+    ///
+    /// ```
+    /// assert_eq!(Vec2::new(0.0, -2.0).normalize(), Vec2::new(0.0, -1.0));
+    /// assert_eq!(Vec2::zero().normalize(), Vec2::zero());
+    /// ```
+    pub fn normalize(&self) -> Vec2 {
+        let length = self.length();
+
+        if length == 0.0 { Vec2::zero() }
+        else { *self / length }
+    }
+
+    pub fn dot(&self, other: Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the cross product of both vectors, positive if
+    /// `other` is clockwise from `self` on screen.
+    pub fn cross(&self, other: Vec2) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn distance(&self, other: Vec2) -> f64 {
+        (other - *self).length()
+    }
+
+    /// Returns the point which is `t` of the way between `self` and `other`,
+    /// where `t` is between 0 and 1.
+    ///
+    /// ```
+    /// assert_eq!(Vec2::new(0.0, 0.0).lerp(Vec2::new(10.0, 20.0), 0.5), Vec2::new(5.0, 10.0));
+    /// ```
+    pub fn lerp(&self, other: Vec2, t: f64) -> Vec2 {
+        *self + (other - *self) * t
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, factor: f64) -> Vec2 {
+        Vec2::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl Div<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn div(self, divisor: f64) -> Vec2 {
+        Vec2::new(self.x / divisor, self.y / divisor)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        *self = *self - other;
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Rectangle {
     pub x: f64,
@@ -10,13 +137,18 @@ pub struct Rectangle {
 
 impl Rectangle {
     /// Generates an SDL rectangle representing `self`.
-    /// Panics if it couldn't be created.
-    pub fn to_sdl(&self) -> Option<SdlRect> {
+    /// Returns `Error::InvalidRectangle` if the rectangle has a negative size,
+    /// or is too thin to cover a single pixel.
+    pub fn to_sdl(&self) -> Result<SdlRect, Error> {
         // The rectangle can't have negative width or height
-        assert!(self.w >= 0.0 && self.h >= 0.0);
+        if self.w < 0.0 || self.h < 0.0 {
+            return Err(Error::InvalidRectangle(*self));
+        }
 
-        SdlRect::new(self.x as i32, self.y as i32, self.w as u32, self.h as u32)
-            .unwrap()
+        match SdlRect::new(self.x as i32, self.y as i32, self.w as u32, self.h as u32) {
+            Ok(Some(rect)) => Ok(rect),
+            _ => Err(Error::InvalidRectangle(*self)),
+        }
     }
 
     /// Returns an Some(Rectangle) if `self` is contained in `parent`,
@@ -51,6 +183,108 @@ impl Rectangle {
         ymax >= self.y && ymax <= self.y + self.h
     }
 
+    /// Returns the region covered by both `self` and `other`, if any.
+    ///
+    /// ```
+    /// let a = Rectangle { x: 0.0, y: 0.0, w: 10.0, h: 10.0 };
+    /// let b = Rectangle { x: 5.0, y: 5.0, w: 10.0, h: 10.0 };
+    /// assert_eq!(a.intersection(b), Some(Rectangle { x: 5.0, y: 5.0, w: 5.0, h: 5.0 }));
+    /// assert_eq!(a.intersection(Rectangle { x: 20.0, ..b }), None);
+    /// ```
+    pub fn intersection(&self, other: Rectangle) -> Option<Rectangle> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.w).min(other.x + other.w);
+        let bottom = (self.y + self.h).min(other.y + other.h);
+
+        if left < right && top < bottom {
+            Some(Rectangle { x: left, y: top, w: right - left, h: bottom - top })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the smallest rectangle containing both `self` and `other`.
+    ///
+    /// ```
+    /// let a = Rectangle { x: 0.0, y: 0.0, w: 10.0, h: 10.0 };
+    /// let b = Rectangle { x: 20.0, y: 5.0, w: 10.0, h: 10.0 };
+    /// assert_eq!(a.union(b), Rectangle { x: 0.0, y: 0.0, w: 30.0, h: 15.0 });
+    /// ```
+    pub fn union(&self, other: Rectangle) -> Rectangle {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x + self.w).max(other.x + other.w);
+        let bottom = (self.y + self.h).max(other.y + other.h);
+
+        Rectangle { x: left, y: top, w: right - left, h: bottom - top }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    /// Returns `self` moved by `offset`.
+    pub fn translate(&self, offset: Vec2) -> Rectangle {
+        Rectangle { x: self.x + offset.x, y: self.y + offset.y, ..*self }
+    }
+
+    /// Returns `self` resized by `factor`, around its center.
+    ///
+    /// ```
+    /// let rect = Rectangle { x: 10.0, y: 10.0, w: 20.0, h: 10.0 };
+    /// assert_eq!(rect.scale(2.0), Rectangle { x: 0.0, y: 5.0, w: 40.0, h: 20.0 });
+    /// ```
+    pub fn scale(&self, factor: f64) -> Rectangle {
+        let center = self.center();
+        let (w, h) = (self.w * factor, self.h * factor);
+
+        Rectangle { x: center.x - w / 2.0, y: center.y - h / 2.0, w: w, h: h }
+    }
+
+    /// Returns `self` grown by `margin` on every side, or shrunk if `margin`
+    /// is negative.
+    pub fn expand(&self, margin: f64) -> Rectangle {
+        Rectangle {
+            x: self.x - margin,
+            y: self.y - margin,
+            w: self.w + margin * 2.0,
+            h: self.h + margin * 2.0,
+        }
+    }
+
+    /// Returns the point of `self` which is the closest to `point`, which is
+    /// `point` itself if it lies inside.
+    ///
+    /// ```
+    /// let rect = Rectangle { x: 0.0, y: 0.0, w: 10.0, h: 10.0 };
+    /// assert_eq!(rect.closest_point(Vec2::new(15.0, 5.0)), Vec2::new(10.0, 5.0));
+    /// assert_eq!(rect.closest_point(Vec2::new(3.0, 4.0)), Vec2::new(3.0, 4.0));
+    /// ```
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            point.x.max(self.x).min(self.x + self.w),
+            point.y.max(self.y).min(self.y + self.h),
+        )
+    }
+
+    /// Whether `point` lies inside of `self`.
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.x >= self.x && point.x < self.x + self.w &&
+        point.y >= self.y && point.y < self.y + self.h
+    }
+
+    /// Returns the rectangle which is `t` of the way between `self` and
+    /// `other`, where `t` is between 0 and 1.
+    pub fn lerp(&self, other: Rectangle, t: f64) -> Rectangle {
+        Rectangle {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            w: self.w + (other.w - self.w) * t,
+            h: self.h + (other.h - self.h) * t,
+        }
+    }
+
     pub fn overlaps(&self, other: Rectangle) -> bool {
         self.x < other.x + other.w &&
         self.x + self.w > other.x &&
@@ -58,3 +292,308 @@ impl Rectangle {
         self.y + self.h > other.y
     }
 }
+
+
+/// A disc, for objects which are round rather than boxy.
+///
+/// # Examples
+///
+/// ```
+/// let a = Circle::new(Vec2::new(0.0, 0.0), 5.0);
+/// let b = Circle::new(Vec2::new(8.0, 0.0), 5.0);
+/// assert!(a.overlaps(b));
+/// assert!(!a.overlaps_rect(Rectangle { x: 10.0, y: 10.0, w: 5.0, h: 5.0 }));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f64) -> Circle {
+        Circle { center: center, radius: radius }
+    }
+
+    /// Returns the largest circle which fits in `rect`, at its center.
+    pub fn inscribed(rect: Rectangle) -> Circle {
+        Circle::new(rect.center(), rect.w.min(rect.h) / 2.0)
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        (point - self.center).length_squared() <= self.radius * self.radius
+    }
+
+    pub fn overlaps(&self, other: Circle) -> bool {
+        let radii = self.radius + other.radius;
+        (other.center - self.center).length_squared() < radii * radii
+    }
+
+    pub fn overlaps_rect(&self, rect: Rectangle) -> bool {
+        (rect.closest_point(self.center) - self.center).length_squared() < self.radius * self.radius
+    }
+
+    /// The smallest rectangle containing the circle.
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle {
+            x: self.center.x - self.radius,
+            y: self.center.y - self.radius,
+            w: self.radius * 2.0,
+            h: self.radius * 2.0,
+        }
+    }
+}
+
+/// The straight line going from `start` to `end`, such as the path of a
+/// bullet during a frame.
+///
+/// Raycasts return how far along the segment the first hit happens, from 0 at
+/// `start` to 1 at `end`, which `point_at` turns back into a point.
+///
+/// # Examples
+///
+/// ```
+/// let ray = Segment::new(Vec2::new(0.0, 5.0), Vec2::new(20.0, 5.0));
+/// let wall = Rectangle { x: 10.0, y: 0.0, w: 5.0, h: 10.0 };
+/// assert_eq!(ray.cast_rect(wall), Some(0.5));
+/// assert_eq!(ray.point_at(0.5), Vec2::new(10.0, 5.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Segment {
+    pub fn new(start: Vec2, end: Vec2) -> Segment {
+        Segment { start: start, end: end }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.start.distance(self.end)
+    }
+
+    /// The point which is `t` of the way from `start` to `end`.
+    pub fn point_at(&self, t: f64) -> Vec2 {
+        self.start.lerp(self.end, t)
+    }
+
+    /// Returns the point of the segment which is the closest to `point`.
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let dir = self.end - self.start;
+        let length_squared = dir.length_squared();
+
+        if length_squared == 0.0 {
+            return self.start;
+        }
+
+        let t = ((point - self.start).dot(dir) / length_squared).max(0.0).min(1.0);
+        self.point_at(t)
+    }
+
+    /// Returns where `self` crosses `other`, as a fraction of `self`, if they
+    /// cross. Parallel segments are never considered to cross.
+    ///
+    /// ```
+    /// let a = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(10.0, 10.0));
+    /// let b = Segment::new(Vec2::new(0.0, 10.0), Vec2::new(10.0, 0.0));
+    /// assert_eq!(a.intersection(b), Some(0.5));
+    /// ```
+    pub fn intersection(&self, other: Segment) -> Option<f64> {
+        let r = self.end - self.start;
+        let s = other.end - other.start;
+        let denom = r.cross(s);
+
+        if denom == 0.0 {
+            return None;
+        }
+
+        let offset = other.start - self.start;
+        let t = offset.cross(s) / denom;
+        let u = offset.cross(r) / denom;
+
+        if t >= 0.0 && t <= 1.0 && u >= 0.0 && u <= 1.0 { Some(t) }
+        else { None }
+    }
+
+    /// Returns where `self` first enters `circle`, as a fraction of `self`.
+    /// If the segment starts inside of the circle, this is 0.
+    ///
+    /// ```
+    /// let ray = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+    /// assert_eq!(ray.cast_circle(Circle::new(Vec2::new(8.0, 0.0), 2.0)), Some(0.6));
+    /// ```
+    pub fn cast_circle(&self, circle: Circle) -> Option<f64> {
+        if circle.contains_point(self.start) {
+            return Some(0.0);
+        }
+
+        // Solve |start + t * dir - center|² = radius² for the smallest t.
+        let dir = self.end - self.start;
+        let offset = self.start - circle.center;
+        let a = dir.length_squared();
+        let b = 2.0 * offset.dot(dir);
+        let c = offset.length_squared() - circle.radius * circle.radius;
+        let discriminant = b * b - 4.0 * a * c;
+
+        if a == 0.0 || discriminant < 0.0 {
+            return None;
+        }
+
+        let t = (-b - discriminant.sqrt()) / (2.0 * a);
+
+        if t >= 0.0 && t <= 1.0 { Some(t) }
+        else { None }
+    }
+
+    /// Returns where `self` first enters `rect`, as a fraction of `self`. If
+    /// the segment starts inside of the rectangle, this is 0.
+    pub fn cast_rect(&self, rect: Rectangle) -> Option<f64> {
+        let dir = self.end - self.start;
+        let (mut t_min, mut t_max) = (0.0f64, 1.0f64);
+
+        // The segment must be between both sides of the rectangle, on each
+        // axis, at the same time.
+        for &(start, dir, low, high) in &[
+            (self.start.x, dir.x, rect.x, rect.x + rect.w),
+            (self.start.y, dir.y, rect.y, rect.y + rect.h),
+        ] {
+            if dir == 0.0 {
+                if start < low || start > high {
+                    return None;
+                }
+            } else {
+                let t0 = (low - start) / dir;
+                let t1 = (high - start) / dir;
+                t_min = t_min.max(t0.min(t1));
+                t_max = t_max.min(t0.max(t1));
+
+                if t_min > t_max {
+                    return None;
+                }
+            }
+        }
+
+        Some(t_min)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Circle, Rectangle, Segment, Vec2};
+
+    fn rect(x: f64, y: f64, w: f64, h: f64) -> Rectangle {
+        Rectangle { x: x, y: y, w: w, h: h }
+    }
+
+    #[test]
+    fn normalizing_the_zero_vector_gives_zero() {
+        assert_eq!(Vec2::zero().normalize(), Vec2::zero());
+        assert_eq!(Vec2::new(3.0, 4.0).normalize(), Vec2::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn lerp_goes_from_self_to_other() {
+        let (a, b) = (Vec2::new(2.0, -4.0), Vec2::new(6.0, 4.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.25), Vec2::new(3.0, -2.0));
+
+        let (r, s) = (rect(0.0, 0.0, 10.0, 10.0), rect(10.0, 20.0, 30.0, 10.0));
+        assert_eq!(r.lerp(s, 0.5), rect(5.0, 10.0, 20.0, 10.0));
+    }
+
+    #[test]
+    fn union_covers_both_rectangles() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.union(a), a);
+        assert_eq!(a.union(rect(2.0, 2.0, 2.0, 2.0)), a);
+        assert_eq!(a.union(rect(-5.0, 15.0, 5.0, 5.0)), rect(-5.0, 0.0, 15.0, 20.0));
+    }
+
+    #[test]
+    fn rectangles_touching_on_an_edge_dont_intersect() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.intersection(rect(10.0, 0.0, 10.0, 10.0)), None);
+        assert_eq!(a.intersection(rect(0.0, 10.0, 10.0, 10.0)), None);
+        assert_eq!(a.intersection(rect(10.0, 10.0, 10.0, 10.0)), None);
+        assert!(!a.overlaps(rect(10.0, 0.0, 10.0, 10.0)));
+
+        assert_eq!(a.intersection(rect(9.0, 0.0, 10.0, 10.0)), Some(rect(9.0, 0.0, 1.0, 10.0)));
+    }
+
+    #[test]
+    fn closest_point_of_a_rectangle() {
+        let r = rect(0.0, 0.0, 10.0, 10.0);
+
+        // Inside, the point is its own closest point.
+        assert_eq!(r.closest_point(Vec2::new(3.0, 7.0)), Vec2::new(3.0, 7.0));
+
+        // Outside, it is moved onto the nearest side or corner.
+        assert_eq!(r.closest_point(Vec2::new(-5.0, 4.0)), Vec2::new(0.0, 4.0));
+        assert_eq!(r.closest_point(Vec2::new(15.0, 15.0)), Vec2::new(10.0, 10.0));
+    }
+
+    #[test]
+    fn closest_point_of_a_segment() {
+        let segment = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        assert_eq!(segment.closest_point(Vec2::new(4.0, 0.0)), Vec2::new(4.0, 0.0));
+        assert_eq!(segment.closest_point(Vec2::new(4.0, 3.0)), Vec2::new(4.0, 0.0));
+        assert_eq!(segment.closest_point(Vec2::new(-4.0, 3.0)), Vec2::new(0.0, 0.0));
+        assert_eq!(segment.closest_point(Vec2::new(14.0, -3.0)), Vec2::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn circle_overlaps_rect() {
+        let circle = Circle::new(Vec2::new(0.0, 0.0), 5.0);
+
+        // The center is inside of the rectangle.
+        assert!(circle.overlaps_rect(rect(-1.0, -1.0, 2.0, 2.0)));
+
+        // A side crosses the circle.
+        assert!(circle.overlaps_rect(rect(4.0, -10.0, 10.0, 20.0)));
+
+        // Only the corner of the bounds of the circle reaches the rectangle.
+        assert!(!circle.overlaps_rect(rect(4.0, 4.0, 10.0, 10.0)));
+
+        // Touching isn't overlapping.
+        assert!(!circle.overlaps_rect(rect(5.0, -1.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn segments_starting_inside_hit_at_zero() {
+        let segment = Segment::new(Vec2::new(1.0, 1.0), Vec2::new(20.0, 1.0));
+        assert_eq!(segment.cast_circle(Circle::new(Vec2::new(0.0, 0.0), 5.0)), Some(0.0));
+        assert_eq!(segment.cast_rect(rect(0.0, 0.0, 5.0, 5.0)), Some(0.0));
+    }
+
+    #[test]
+    fn parallel_segments_never_cross() {
+        let a = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+        assert_eq!(a.intersection(Segment::new(Vec2::new(0.0, 5.0), Vec2::new(10.0, 5.0))), None);
+
+        // Not even when they lie on the same line.
+        assert_eq!(a.intersection(Segment::new(Vec2::new(5.0, 0.0), Vec2::new(15.0, 0.0))), None);
+
+        // A segment parallel to a side of a rectangle still hits it.
+        assert_eq!(a.cast_rect(rect(5.0, -1.0, 2.0, 2.0)), Some(0.5));
+        assert_eq!(a.cast_rect(rect(5.0, 1.0, 2.0, 2.0)), None);
+    }
+
+    #[test]
+    fn zero_length_segments() {
+        let point = Segment::new(Vec2::new(2.0, 2.0), Vec2::new(2.0, 2.0));
+        assert_eq!(point.length(), 0.0);
+        assert_eq!(point.closest_point(Vec2::new(10.0, 10.0)), Vec2::new(2.0, 2.0));
+
+        // They never cross another segment.
+        assert_eq!(point.intersection(Segment::new(Vec2::new(0.0, 2.0), Vec2::new(4.0, 2.0))), None);
+
+        // They only hit shapes which they lie inside of.
+        assert_eq!(point.cast_circle(Circle::new(Vec2::new(0.0, 0.0), 5.0)), Some(0.0));
+        assert_eq!(point.cast_circle(Circle::new(Vec2::new(10.0, 10.0), 5.0)), None);
+        assert_eq!(point.cast_rect(rect(0.0, 0.0, 5.0, 5.0)), Some(0.0));
+        assert_eq!(point.cast_rect(rect(5.0, 5.0, 5.0, 5.0)), None);
+    }
+}
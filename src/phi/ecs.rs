@@ -0,0 +1,277 @@
+use phi::Phi;
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+
+/// A handle to a game object, which is nothing more than the set of
+/// components attached to it in a `World`.
+///
+/// Indices are reused once an entity is destroyed, but the generation tells
+/// the old entity apart from the new one, so that stale handles don't find
+/// the components of whatever took their place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+/// Holds the components of type `T`, indexed by entity.
+pub struct Storage<T> {
+    slots: Vec<Option<(Entity, T)>>,
+    len: usize,
+}
+
+impl<T> Storage<T> {
+    pub fn new() -> Storage<T> {
+        Storage {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Attaches `component` to `entity`, returning the one it replaces.
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        let index = entity.index as usize;
+
+        while self.slots.len() <= index {
+            self.slots.push(None);
+        }
+
+        let previous = self.slots[index].take()
+            .and_then(|(owner, old)| if owner == entity { Some(old) } else { None });
+
+        if previous.is_none() {
+            self.len += 1;
+        }
+
+        self.slots[index] = Some((entity, component));
+        previous
+    }
+
+    /// Detaches the component of `entity`, if it has one.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        if !self.contains(entity) {
+            return None;
+        }
+
+        self.len -= 1;
+        self.slots[entity.index as usize].take().map(|(_, component)| component)
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index as usize) {
+            Some(&Some((owner, ref component))) if owner == entity => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index as usize) {
+            Some(&mut Some((owner, ref mut component))) if owner == entity => Some(component),
+            _ => None,
+        }
+    }
+
+    /// The number of entities which have this component.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the entities which have this component, in the order of
+    /// their indices.
+    pub fn iter<'a>(&'a self) -> Box<Iterator<Item=(Entity, &'a T)> + 'a> {
+        Box::new(self.slots.iter().filter_map(|slot| {
+            slot.as_ref().map(|&(entity, ref component)| (entity, component))
+        }))
+    }
+
+    pub fn iter_mut<'a>(&'a mut self) -> Box<Iterator<Item=(Entity, &'a mut T)> + 'a> {
+        Box::new(self.slots.iter_mut().filter_map(|slot| {
+            slot.as_mut().map(|&mut (entity, ref mut component)| (entity, component))
+        }))
+    }
+}
+
+/// Lets the world handle storages without knowing the type of their
+/// components.
+trait AnyStorage {
+    /// Drops the component of `entity`, if it has one.
+    fn remove_entity(&self, entity: Entity);
+
+    fn as_any(&self) -> &Any;
+}
+
+impl<T: 'static> AnyStorage for RefCell<Storage<T>> {
+    fn remove_entity(&self, entity: Entity) {
+        self.borrow_mut().remove(entity);
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+}
+
+/// Owns every entity, along with their components.
+///
+/// Every storage sits in its own `RefCell`, so that a system can modify a
+/// component while reading another, for example moving positions according
+/// to velocities. Borrowing the same storage mutably twice panics.
+///
+/// # Examples
+///
+/// ```
+/// let mut world = World::new();
+/// let ship = world.create();
+/// world.insert(ship, Position { x: 64.0, y: 64.0 });
+/// world.insert(ship, Velocity { x: 180.0, y: 0.0 });
+///
+/// let velocities = world.read::<Velocity>();
+/// for (entity, pos) in world.write::<Position>().iter_mut() {
+///     if let Some(vel) = velocities.get(entity) {
+///         pos.x += vel.x * elapsed;
+///         pos.y += vel.y * elapsed;
+///     }
+/// }
+/// ```
+pub struct World {
+    /// The current generation of every index
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+
+    /// The indices of the destroyed entities, which can be reused
+    free: Vec<u32>,
+
+    storages: HashMap<TypeId, Box<AnyStorage>>,
+}
+
+impl World {
+    pub fn new() -> World {
+        World {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            storages: HashMap::new(),
+        }
+    }
+
+    /// Creates an entity without any component.
+    pub fn create(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity { index: index, generation: self.generations[index as usize] }
+            },
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity { index: self.generations.len() as u32 - 1, generation: 0 }
+            },
+        }
+    }
+
+    /// Drops `entity` along with its components. Returns whether it was alive.
+    pub fn destroy(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        for storage in self.storages.values() {
+            storage.remove_entity(entity);
+        }
+
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        index < self.alive.len() && self.alive[index] && self.generations[index] == entity.generation
+    }
+
+    /// The number of entities which are alive.
+    pub fn len(&self) -> usize {
+        self.alive.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Makes room for components of type `T`. This is done by `insert`, but
+    /// must be done explicitly before reading components which might never
+    /// have been inserted.
+    pub fn register<T: 'static>(&mut self) {
+        self.storages.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(RefCell::new(Storage::<T>::new())));
+    }
+
+    /// Attaches `component` to `entity`, replacing the one of the same type.
+    /// Panics if the entity was destroyed.
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) {
+        assert!(self.is_alive(entity), "Inserted a component into a destroyed entity");
+
+        self.register::<T>();
+        self.write::<T>().insert(entity, component);
+    }
+
+    /// Detaches the component of type `T` from `entity`, if it has one.
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        match self.storage::<T>() {
+            Some(storage) => storage.borrow_mut().remove(entity),
+            None => None,
+        }
+    }
+
+    /// Borrows the components of type `T`. Panics if they are being
+    /// modified, or if the type was never registered.
+    pub fn read<T: 'static>(&self) -> Ref<Storage<T>> {
+        self.storage::<T>().expect("Read an unregistered component").borrow()
+    }
+
+    /// Borrows the components of type `T` mutably. Panics if they are being
+    /// read or modified, or if the type was never registered.
+    pub fn write<T: 'static>(&self) -> RefMut<Storage<T>> {
+        self.storage::<T>().expect("Wrote an unregistered component").borrow_mut()
+    }
+
+    /// Returns the entities which have a component of type `T`.
+    pub fn query<T: 'static>(&self) -> Vec<Entity> {
+        match self.storage::<T>() {
+            Some(storage) => storage.borrow().iter().map(|(entity, _)| entity).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the entities which have components of both types `A` and `B`.
+    pub fn query2<A: 'static, B: 'static>(&self) -> Vec<Entity> {
+        match self.storage::<B>() {
+            Some(storage) => {
+                let storage = storage.borrow();
+                self.query::<A>().into_iter().filter(|&entity| storage.contains(entity)).collect()
+            },
+            None => Vec::new(),
+        }
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&RefCell<Storage<T>>> {
+        self.storages.get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref::<RefCell<Storage<T>>>())
+    }
+}
+
+/// Updates the components of a world, for example moving every entity which
+/// has both a position and a velocity.
+pub trait System {
+    fn run(&mut self, world: &mut World, phi: &mut Phi, elapsed: f64);
+}
@@ -0,0 +1,52 @@
+use phi::data::Rectangle;
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The ways in which the engine can fail.
+#[derive(Debug)]
+pub enum Error {
+    /// SDL, or one of its extensions, reported an error, for example while
+    /// initializing a subsystem or creating the window.
+    Sdl(String),
+
+    /// There is no asset at the given path.
+    AssetNotFound(PathBuf),
+
+    /// The asset at the given path exists, but couldn't be decoded.
+    Decode(PathBuf, String),
+
+    /// The file at the given path couldn't be read or written.
+    Io(PathBuf, io::Error),
+
+    /// A font couldn't be loaded, or couldn't render some text.
+    Font(String),
+
+    /// The rectangle can't be used for drawing, either because it has a
+    /// negative size, or because it doesn't fit in its parent.
+    InvalidRectangle(Rectangle),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Sdl(ref msg) => write!(f, "SDL error: {}", msg),
+            Error::AssetNotFound(ref path) => write!(f, "asset not found: {}", path.display()),
+            Error::Decode(ref path, ref msg) => write!(f, "could not decode {}: {}", path.display(), msg),
+            Error::Io(ref path, ref error) => write!(f, "could not access {}: {}", path.display(), error),
+            Error::Font(ref msg) => write!(f, "font error: {}", msg),
+            Error::InvalidRectangle(rect) => write!(f, "invalid rectangle: {:?}", rect),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// SDL reports its errors as strings, which allows us to use `?` directly on
+/// the result of most of its functions.
+impl From<String> for Error {
+    fn from(msg: String) -> Error {
+        Error::Sdl(msg)
+    }
+}
@@ -5,11 +5,69 @@ macro_rules! struct_events {
         else: { $( $e_alias:ident : $e_sdl:pat ),* }
     ) => {
         use sdl2::EventPump;
+        use sdl2::GameControllerSubsystem;
+        use sdl2::controller::{Axis, Button, GameController};
+        use sdl2::event::Event;
+        use sdl2::keyboard::Keycode;
+        use sdl2::mouse::MouseButton;
+        // `Error`, `HashMap`, `Player`, `Recorder` and `Screen` are already
+        // imported by the module which invokes the macro, and can't be
+        // imported twice.
+        use phi::replay::Input;
+        use std::collections::HashSet;
 
         pub struct ImmediateEvents {
             $( pub $k_alias: Option<bool>, )*
             $( pub $e_alias: bool, )*
 
+            /// Whether a controller was plugged in during this frame
+            pub controller_added: bool,
+
+            /// Whether a controller was unplugged during this frame
+            pub controller_removed: bool,
+
+            /// Every key which was pressed or released during this frame,
+            /// including the ones which don't have an alias
+            pub keys_pressed: HashSet<Keycode>,
+            pub keys_released: HashSet<Keycode>,
+
+            /// Every key which the system repeated during this frame, because
+            /// it is held down
+            pub keys_repeated: HashSet<Keycode>,
+
+            /// Every controller button which was pressed or released during
+            /// this frame, including the ones which don't have an alias
+            pub buttons_pressed: HashSet<Button>,
+            pub buttons_released: HashSet<Button>,
+
+            /// The position, at the start of this frame, of every axis which
+            /// moved during this frame
+            pub axes_moved: HashMap<Axis, f64>,
+
+            /// Whether the mouse moved during this frame
+            pub mouse_moved: bool,
+
+            /// Every mouse button which was pressed or released during this
+            /// frame
+            pub mouse_pressed: HashSet<MouseButton>,
+            pub mouse_released: HashSet<MouseButton>,
+
+            /// Every mouse button press of this frame, in order, along with the
+            /// position of the cursor when it happened
+            pub clicks: Vec<(MouseButton, (f64, f64))>,
+
+            /// How far the wheel was scrolled during this frame, horizontally
+            /// and vertically. Positive values go right, and away from the user.
+            pub wheel: (i32, i32),
+
+            /// The text which was typed during this frame, while text input
+            /// was active
+            pub text_input: String,
+
+            /// Whether the text being composed, stored in `Events::composition`,
+            /// changed during this frame
+            pub composition_changed: bool,
+
             resize: Option<(u32, u32)>,
         }
 
@@ -19,6 +77,25 @@ macro_rules! struct_events {
                     $( $k_alias: None, )*
                     $( $e_alias: false, )*
 
+                    controller_added: false,
+                    controller_removed: false,
+
+                    keys_pressed: HashSet::new(),
+                    keys_released: HashSet::new(),
+                    keys_repeated: HashSet::new(),
+                    buttons_pressed: HashSet::new(),
+                    buttons_released: HashSet::new(),
+                    axes_moved: HashMap::new(),
+
+                    mouse_moved: false,
+                    mouse_pressed: HashSet::new(),
+                    mouse_released: HashSet::new(),
+                    clicks: Vec::new(),
+                    wheel: (0, 0),
+
+                    text_input: String::new(),
+                    composition_changed: false,
+
                     resize: None,
                 }
             }
@@ -28,65 +105,411 @@ macro_rules! struct_events {
             pump: EventPump,
             pub now: ImmediateEvents,
 
+            /// Used to open the controllers as they are plugged in
+            controller_subsystem: GameControllerSubsystem,
+
+            /// The controllers which are currently plugged in, by instance id.
+            /// SDL only sends their events while they're open.
+            controllers: HashMap<i32, GameController>,
+
+            /// The buttons held, and the position of the axes which aren't
+            /// centered, on every controller, which `buttons_held` and `axes`
+            /// merge together
+            controller_state: HashMap<i32, (HashSet<Button>, HashMap<Axis, f64>)>,
+
+            /// Events which will be handled on the next call to `pump`, as if
+            /// they had been sent by SDL
+            injected: Vec<Event>,
+
+            /// Saves the input of every frame, when recording a replay
+            recorder: Option<Recorder>,
+
+            /// Provides the input of every frame, when playing a replay
+            player: Option<Player>,
+
+            /// Converts the position of the cursor to the logical screen
+            screen: Screen,
+
+            /// Every key and controller button which is currently held down
+            pub keys_held: HashSet<Keycode>,
+            pub buttons_held: HashSet<Button>,
+
+            /// The position of every axis which moved, between -1.0 and 1.0
+            pub axes: HashMap<Axis, f64>,
+
+            /// The position of the cursor, in logical pixels from the
+            /// top-left corner of the screen
+            pub mouse: (f64, f64),
+
+            /// Every mouse button which is currently held down
+            pub mouse_held: HashSet<MouseButton>,
+
+            /// The text which is being composed through an input method, before
+            /// it is committed to `now.text_input`, along with the position of
+            /// the cursor inside of it
+            pub composition: String,
+            pub composition_cursor: i32,
+
             $( pub $k_alias: bool ),*
         }
 
         impl Events {
-            pub fn new(pump: EventPump) -> Events {
+            pub fn new(pump: EventPump, controller_subsystem: GameControllerSubsystem) -> Events {
                 Events {
                     pump: pump,
                     now: ImmediateEvents::new(),
 
+                    controller_subsystem: controller_subsystem,
+                    controllers: HashMap::new(),
+                    controller_state: HashMap::new(),
+                    injected: Vec::new(),
+
+                    recorder: None,
+                    player: None,
+                    screen: Screen::new(1, 1, false),
+
+                    keys_held: HashSet::new(),
+                    buttons_held: HashSet::new(),
+                    axes: HashMap::new(),
+
+                    mouse: (0.0, 0.0),
+                    mouse_held: HashSet::new(),
+
+                    composition: String::new(),
+                    composition_cursor: 0,
+
                     $( $k_alias: false ),*
                 }
             }
 
-            pub fn pump(&mut self, renderer: &mut ::sdl2::render::Renderer) {
+            /// The number of controllers which are currently plugged in.
+            pub fn controllers(&self) -> usize {
+                self.controllers.len()
+            }
+
+            /// Returns the position of `axis`, between -1.0 and 1.0.
+            pub fn axis(&self, axis: Axis) -> f64 {
+                self.axes.get(&axis).cloned().unwrap_or(0.0)
+            }
+
+            /// Queues `event` so that it is handled on the next call to `pump`,
+            /// exactly as if SDL had sent it. This lets tests simulate input.
+            pub fn inject(&mut self, event: Event) {
+                self.injected.push(event);
+            }
+
+            /// Starts saving the input of every frame through `recorder`.
+            pub fn record(&mut self, recorder: Recorder) {
+                self.recorder = Some(recorder);
+            }
+
+            /// Stops saving the input, and finishes writing the replay.
+            pub fn stop_recording(&mut self) -> Result<(), Error> {
+                match self.recorder.take() {
+                    Some(recorder) => recorder.finish(),
+                    None => Ok(()),
+                }
+            }
+
+            /// Replaces the input of the player by the frames of `player`,
+            /// until it runs out of them.
+            pub fn replay(&mut self, player: Player) {
+                self.player = Some(player);
+            }
+
+            /// Sets the screen to which the cursor positions of the next
+            /// events are converted.
+            pub fn set_screen(&mut self, screen: Screen) {
+                self.screen = screen;
+            }
+
+            /// Whether the input currently comes from a replay.
+            pub fn is_replaying(&self) -> bool {
+                self.player.is_some()
+            }
+
+            /// Handles the events of a new frame. If the frame can't be
+            /// saved to the replay being recorded, the recording stops and
+            /// the error is returned, after the frame was handled.
+            pub fn pump(&mut self) -> Result<(), Error> {
                 self.now = ImmediateEvents::new();
 
-                for event in self.pump.poll_iter() {
-                    use sdl2::event::Event::*;
-                    use sdl2::event::WindowEventId::Resized;
-                    use sdl2::keyboard::Keycode::*;
+                let mut events: Vec<Event> = self.pump.poll_iter().collect();
+                events.extend(self.injected.drain(..));
 
-                    match event {
-                        Window { win_event_id: Resized, .. } => {
-                            self.now.resize = Some(renderer.output_size().unwrap());
-                        },
+                let live: Vec<Input> = events.into_iter()
+                    .flat_map(|event| self.handle(event))
+                    .collect();
 
-                        KeyDown { keycode, .. } => match keycode {
-                            $(
-                                Some($k_sdl) => {
-                                    if !self.$k_alias {
-                                        self.now.$k_alias = Some(true);
-                                    }
+                // While a replay is playing, the input of the player is
+                // ignored, but the window still reacts to being resized, and
+                // the flags such as `quit` still come through.
+                let frame = match self.player.as_mut().map(Player::next_frame) {
+                    Some(Some(mut frame)) => {
+                        frame.extend(live.into_iter().filter(|input| match *input {
+                            Input::Flag(_) => true,
+                            _ => false,
+                        }));
 
-                                    self.$k_alias = true;
-                                }
-                            ),*
-                            _ => {}
-                        },
-
-                        KeyUp { keycode, .. } => match keycode {
-                            $(
-                                Some($k_sdl) => {
-                                    self.now.$k_alias = Some(false);
-                                    self.$k_alias = false;
+                        frame
+                    },
+                    Some(None) => {
+                        self.player = None;
+                        live
+                    },
+                    None => live,
+                };
+
+                let recorded = match self.recorder {
+                    Some(ref mut recorder) => recorder.record(&frame),
+                    None => Ok(()),
+                };
+
+                if recorded.is_err() {
+                    self.recorder = None;
+                }
+
+                for input in frame {
+                    self.apply(input);
+                }
+
+                recorded
+            }
+
+            /// Reacts to the events which concern the window and the devices,
+            /// and returns the input carried by the other ones.
+            fn handle(&mut self, event: Event) -> Vec<Input> {
+                use sdl2::event::Event::*;
+                use sdl2::event::WindowEventId::Resized;
+
+                match event {
+                    Window { win_event_id: Resized, data1, data2, .. } => {
+                        self.now.resize = Some((data1 as u32, data2 as u32));
+                        vec![]
+                    },
+
+                    ControllerDeviceAdded { which, .. } => {
+                        // `which` is the index of the device, which differs
+                        // from the id used in the events of the controller.
+                        if let Ok(controller) = self.controller_subsystem.open(which as u32) {
+                            self.controllers.insert(controller.instance_id(), controller);
+                        }
+
+                        self.now.controller_added = true;
+                        vec![]
+                    },
+
+                    ControllerDeviceRemoved { which, .. } => {
+                        self.controllers.remove(&which);
+                        self.now.controller_removed = true;
+
+                        // Whatever it held is released, as if the player had
+                        // let go of it.
+                        self.release_controller(which)
+                    },
+
+                    $(
+                        $e_sdl => vec![Input::Flag(stringify!($e_alias).to_string())],
+                    )*
+
+                    event => Input::from_event(event)
+                        .map(|input| self.to_logical(input))
+                        .into_iter()
+                        .collect(),
+                }
+            }
+
+            /// Converts the cursor position carried by `input` to the logical
+            /// screen, so that replays don't depend on the size of the window.
+            fn to_logical(&self, input: Input) -> Input {
+                let convert = |x: i32, y: i32| {
+                    let (x, y) = self.screen.to_logical((x as f64, y as f64));
+                    (x.round() as i32, y.round() as i32)
+                };
+
+                match input {
+                    Input::MouseMotion(x, y) => {
+                        let (x, y) = convert(x, y);
+                        Input::MouseMotion(x, y)
+                    },
+                    Input::MouseDown(button, x, y) => {
+                        let (x, y) = convert(x, y);
+                        Input::MouseDown(button, x, y)
+                    },
+                    Input::MouseUp(button, x, y) => {
+                        let (x, y) = convert(x, y);
+                        Input::MouseUp(button, x, y)
+                    },
+                    input => input,
+                }
+            }
+
+            /// Returns the input which releases the buttons and centers the
+            /// axes of an unplugged controller.
+            fn release_controller(&mut self, which: i32) -> Vec<Input> {
+                let (buttons, axes) = match self.controller_state.get(&which) {
+                    Some(state) => state.clone(),
+                    None => return vec![],
+                };
+
+                let released = buttons.into_iter()
+                    .map(|button| Input::ButtonUp(which, button));
+
+                let centered = axes.into_iter()
+                    .map(|(axis, _)| Input::AxisMotion(which, axis, 0));
+
+                released.chain(centered).collect()
+            }
+
+            /// Whether any controller holds `button`.
+            fn any_holds(&self, button: Button) -> bool {
+                self.controller_state.values().any(|state| state.0.contains(&button))
+            }
+
+            /// The position of `axis` which is the furthest from the center,
+            /// among every controller.
+            fn furthest(&self, axis: Axis) -> f64 {
+                self.controller_state.values()
+                    .filter_map(|state| state.1.get(&axis))
+                    .fold(0.0, |acc: f64, &value| if value.abs() > acc.abs() { value } else { acc })
+            }
+
+            /// Updates the state of `self` according to a single input.
+            fn apply(&mut self, input: Input) {
+                use sdl2::keyboard::Keycode::*;
+
+                match input {
+                    Input::KeyDown(keycode) => {
+                        if self.keys_held.insert(keycode) {
+                            self.now.keys_pressed.insert(keycode);
+                        }
+
+                        match keycode {
+                        $(
+                            $k_sdl => {
+                                if !self.$k_alias {
+                                    self.now.$k_alias = Some(true);
                                 }
-                            ),*
-                            _ => {}
-                        },
 
+                                self.$k_alias = true;
+                            }
+                        ),*
+                        _ => {}
+                        }
+                    },
+
+                    Input::KeyUp(keycode) => {
+                        self.keys_held.remove(&keycode);
+                        self.now.keys_released.insert(keycode);
+
+                        match keycode {
                         $(
-                            $e_sdl => {
-                                self.now.$e_alias = true;
+                            $k_sdl => {
+                                self.now.$k_alias = Some(false);
+                                self.$k_alias = false;
                             }
                         ),*
+                        _ => {}
+                        }
+                    },
+
+                    Input::KeyRepeat(keycode) => {
+                        self.now.keys_repeated.insert(keycode);
+                    },
+
+                    Input::ButtonDown(which, button) => {
+                        self.controller_state.entry(which).or_insert_with(Default::default)
+                            .0.insert(button);
+
+                        if self.buttons_held.insert(button) {
+                            self.now.buttons_pressed.insert(button);
+                        }
+                    },
+
+                    Input::ButtonUp(which, button) => {
+                        if let Some(state) = self.controller_state.get_mut(&which) {
+                            state.0.remove(&button);
+                        }
+
+                        // The button stays held while another controller
+                        // holds it.
+                        if !self.any_holds(button) && self.buttons_held.remove(&button) {
+                            self.now.buttons_released.insert(button);
+                        }
+                    },
+
+                    Input::AxisMotion(which, axis, value) => {
+                        // The negative side goes one step further than the
+                        // positive one.
+                        let value = (value as f64 / 32_767.0).max(-1.0);
+                        let before = self.axis(axis);
 
+                        {
+                            let axes = &mut self.controller_state.entry(which)
+                                .or_insert_with(Default::default).1;
+
+                            if value == 0.0 { axes.remove(&axis); }
+                            else { axes.insert(axis, value); }
+                        }
+
+                        // Every controller can move the axis, and the one
+                        // which pushes it the furthest wins.
+                        let value = self.furthest(axis);
+                        self.now.axes_moved.entry(axis).or_insert(before);
+                        self.axes.insert(axis, value);
+                    },
+
+                    Input::MouseMotion(x, y) => {
+                        self.mouse = (x as f64, y as f64);
+                        self.now.mouse_moved = true;
+                    },
+
+                    Input::MouseDown(button, x, y) => {
+                        self.mouse = (x as f64, y as f64);
+
+                        if self.mouse_held.insert(button) {
+                            self.now.mouse_pressed.insert(button);
+                        }
+
+                        self.now.clicks.push((button, self.mouse));
+                    },
+
+                    Input::MouseUp(button, x, y) => {
+                        self.mouse = (x as f64, y as f64);
+                        self.mouse_held.remove(&button);
+                        self.now.mouse_released.insert(button);
+                    },
+
+                    Input::MouseWheel(x, y) => {
+                        self.now.wheel.0 += x;
+                        self.now.wheel.1 += y;
+                    },
+
+                    Input::Text(text) => {
+                        self.now.text_input.push_str(&text);
+
+                        // Committing the text ends the composition.
+                        if !self.composition.is_empty() {
+                            self.composition.clear();
+                            self.composition_cursor = 0;
+                            self.now.composition_changed = true;
+                        }
+                    },
+
+                    Input::Editing(text, start) => {
+                        self.composition = text;
+                        self.composition_cursor = start;
+                        self.now.composition_changed = true;
+                    },
+
+                    Input::Flag(alias) => match &alias[..] {
+                        $(
+                            stringify!($e_alias) => self.now.$e_alias = true,
+                        )*
                         _ => {}
-                    }
+                    },
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
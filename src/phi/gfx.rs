@@ -1,12 +1,115 @@
-use phi::data::Rectangle;
+use phi::Error;
+use phi::data::{Rectangle, Vec2};
 use std::cell::RefCell;
 use std::path::Path;
 use std::rc::Rc;
-use sdl2::render::{Renderer, Texture};
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+use sdl2::render::{BlendMode, Renderer, Texture};
 use sdl2_image::LoadTexture;
 
+/// Describes how a sprite is transformed and colored when it's drawn.
+///
+/// # Examples
+///
+/// ```
+/// // A ship facing left, slightly tilted, which flashes red when it's hit
+/// let params = DrawParams::new()
+///     .angle(15.0)
+///     .flip_horizontal(true)
+///     .color(Color::RGB(255, 80, 80));
+///
+/// phi.renderer.copy_sprite_ex(&ship, dest, &params);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawParams {
+    /// The rotation of the sprite, in degrees, clockwise
+    pub angle: f64,
+
+    /// The point around which the sprite rotates, relative to the top-left
+    /// corner of its destination. When `None`, this is its center.
+    pub pivot: Option<Vec2>,
+
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+
+    /// The opacity of the sprite, from 0 (invisible) to 255 (opaque)
+    pub alpha: u8,
+
+    /// The color by which every pixel of the sprite is multiplied, which is
+    /// white to keep the original colors
+    pub color: Color,
+
+    /// How the sprite is mixed with what is already drawn. When `None`, the
+    /// blend mode of its texture is kept.
+    pub blend: Option<BlendMode>,
+}
+
+impl DrawParams {
+    /// Creates the parameters which draw a sprite as it is.
+    pub fn new() -> DrawParams {
+        DrawParams {
+            angle: 0.0,
+            pivot: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+            alpha: 255,
+            color: Color::RGB(255, 255, 255),
+            blend: None,
+        }
+    }
+
+    pub fn angle(mut self, angle: f64) -> DrawParams {
+        self.angle = angle;
+        self
+    }
+
+    pub fn pivot(mut self, pivot: Vec2) -> DrawParams {
+        self.pivot = Some(pivot);
+        self
+    }
+
+    pub fn flip_horizontal(mut self, flip: bool) -> DrawParams {
+        self.flip_horizontal = flip;
+        self
+    }
+
+    pub fn flip_vertical(mut self, flip: bool) -> DrawParams {
+        self.flip_vertical = flip;
+        self
+    }
+
+    pub fn alpha(mut self, alpha: u8) -> DrawParams {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> DrawParams {
+        self.color = color;
+        self
+    }
+
+    pub fn blend(mut self, blend: BlendMode) -> DrawParams {
+        self.blend = Some(blend);
+        self
+    }
+}
+
+impl Default for DrawParams {
+    fn default() -> DrawParams {
+        DrawParams::new()
+    }
+}
+
 pub trait Renderable {
-    fn render(&self, renderer: &mut Renderer, dest: Rectangle);
+    /// Draws `self` as it is, stretched to fit `dest`.
+    fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
+        self.render_ex(renderer, dest, &DrawParams::new());
+    }
+
+    /// Draws `self`, stretched to fit `dest`, then transformed and colored
+    /// according to `params`.
+    fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, params: &DrawParams);
 }
 
 #[derive(Clone)]
@@ -18,10 +121,16 @@ pub struct Sprite {
 impl Sprite {
     /// Creates a new sprite by wrapping `Texture`
     pub fn new(texture: Texture) -> Sprite {
-        let tex_query = texture.query();
+        Sprite::from_shared(Rc::new(RefCell::new(texture)))
+    }
+
+    /// Creates a new sprite showing the whole of a texture which may already
+    /// be used by other sprites.
+    pub fn from_shared(tex: Rc<RefCell<Texture>>) -> Sprite {
+        let tex_query = tex.borrow().query();
 
         Sprite {
-            tex: Rc::new(RefCell::new(texture)),
+            tex: tex,
             src: Rectangle {
                 x: 0.0,
                 y: 0.0,
@@ -32,16 +141,25 @@ impl Sprite {
     }
 
     /// Creates a new sprite from an image file located at the given path.
-    /// Returns `Some(Sprite)` if the file could be read, `None` otherwise.
-    pub fn load(renderer: &Renderer, path: &str) -> Option<Sprite> {
-        renderer.load_texture(Path::new(path)).ok().map(Sprite::new)
+    /// Returns `Error::AssetNotFound` if there is no such file, and
+    /// `Error::Decode` if it isn't an image we can read.
+    pub fn load(renderer: &Renderer, path: &str) -> Result<Sprite, Error> {
+        let path = Path::new(path);
+
+        if !path.exists() {
+            return Err(Error::AssetNotFound(path.to_path_buf()));
+        }
+
+        renderer.load_texture(path)
+            .map(Sprite::new)
+            .map_err(|msg| Error::Decode(path.to_path_buf(), msg))
     }
 
     /// Returns a new `Sprite` representing a sub-region of the current one.
     /// The provided `rect` is relative to the currently held region.
-    /// Returns `Some(Sprite)` if `rect` is valid, i.e. included in the current
-    /// region; returns `None` otherwise.
-    pub fn region(&self, rect: Rectangle) -> Option<Sprite> {
+    /// Returns `Error::InvalidRectangle` if `rect` isn't included in the
+    /// current region.
+    pub fn region(&self, rect: Rectangle) -> Result<Sprite, Error> {
         let new_src = Rectangle {
             x: self.src.x + rect.x,
             y: self.src.y + rect.y,
@@ -49,12 +167,12 @@ impl Sprite {
         };
 
         if self.src.contains(new_src) {
-            Some(Sprite {
+            Ok(Sprite {
                 tex: self.tex.clone(),
                 src: new_src,
             })
         } else {
-            None
+            Err(Error::InvalidRectangle(rect))
         }
     }
 
@@ -62,11 +180,46 @@ impl Sprite {
     pub fn size(&self) -> (f64, f64) {
         (self.src.w, self.src.h)
     }
+
+    /// Returns the texture holding the sprite, which is shared with every
+    /// sprite cloned or derived from it.
+    pub fn texture(&self) -> &Rc<RefCell<Texture>> {
+        &self.tex
+    }
 }
 
 impl Renderable for Sprite {
-    fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
-        renderer.copy(&mut self.tex.borrow_mut(), self.src.to_sdl(), dest.to_sdl());
+    fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, params: &DrawParams) {
+        // A destination which doesn't cover any pixel has nothing to show.
+        if let (Ok(src), Ok(sdl_dest)) = (self.src.to_sdl(), dest.to_sdl()) {
+            let mut tex = self.tex.borrow_mut();
+
+            // The texture is shared with other sprites, so its modulation is
+            // set again on every draw.
+            tex.set_alpha_mod(params.alpha);
+            let (r, g, b) = params.color.rgb();
+            tex.set_color_mod(r, g, b);
+
+            // The blend mode, on the other hand, is only overridden for this
+            // draw, so that the texture keeps its own.
+            let previous_blend = params.blend.map(|blend| {
+                let previous = tex.blend_mode();
+                tex.set_blend_mode(blend);
+                previous
+            });
+
+            let pivot = params.pivot.unwrap_or(Vec2::new(dest.w / 2.0, dest.h / 2.0));
+
+            // As with `copy`, a failed draw only leaves the sprite out of this
+            // frame.
+            let _ = renderer.copy_ex(&tex, Some(src), Some(sdl_dest), params.angle,
+                                     Some(Point::new(pivot.x as i32, pivot.y as i32)),
+                                     params.flip_horizontal, params.flip_vertical);
+
+            if let Some(blend) = previous_blend {
+                tex.set_blend_mode(blend);
+            }
+        }
     }
 }
 
@@ -105,6 +258,11 @@ impl AnimatedSprite {
         self.sprites.len()
     }
 
+    /// The index of the frame which is currently shown
+    pub fn current_frame(&self) -> usize {
+        (self.current_time / self.frame_delay) as usize % self.frames()
+    }
+
     /// Set the time it takes to get from one frame to the next one, in second.
     /// If the time is negative, then we rewind the animation.
     pub fn set_frame_delay(&mut self, frame_delay: f64) {
@@ -136,19 +294,187 @@ impl AnimatedSprite {
 
 impl Renderable for AnimatedSprite {
     /// Renders the current frame of the sprite
-    fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
-        let current_frame = (self.current_time / self.frame_delay) as usize % self.frames();
-        let sprite = &self.sprites[current_frame];
-        sprite.render(renderer, dest);
+    fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, params: &DrawParams) {
+        let sprite = &self.sprites[self.current_frame()];
+        sprite.render_ex(renderer, dest, params);
+    }
+}
+
+/// Decides which part of the world is shown on the screen, and how large.
+///
+/// Entities are positioned in world coordinates, and drawn through
+/// `CopySprite::copy_sprite_world`; whatever is part of the interface, such as
+/// the score, is still drawn in screen coordinates through `copy_sprite`.
+///
+/// # Examples
+///
+/// ```
+/// let mut camera = Camera::new(800.0, 600.0)
+///     .with_bounds(Rectangle { x: 0.0, y: 0.0, w: 4000.0, h: 600.0 });
+///
+/// // On every update
+/// camera.follow(ship_pos.center(), 5.0, elapsed);
+///
+/// // On every frame
+/// phi.renderer.copy_sprite_world(&ship_sprite, &camera, ship_pos);
+/// phi.renderer.copy_sprite(&score_sprite, score_rect);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// The point of the world shown at the center of the screen
+    pub center: Vec2,
+
+    /// The number of screen pixels covered by a unit of the world
+    pub zoom: f64,
+
+    /// The size of the region of the screen in which the world is drawn
+    pub screen_size: (f64, f64),
+
+    /// The region of the world out of which the camera never looks
+    pub bounds: Option<Rectangle>,
+}
+
+impl Camera {
+    /// Creates a camera showing the world exactly as if it was drawn directly
+    /// on a `width`x`height` screen.
+    pub fn new(width: f64, height: f64) -> Camera {
+        Camera {
+            center: Vec2::new(width / 2.0, height / 2.0),
+            zoom: 1.0,
+            screen_size: (width, height),
+            bounds: None,
+        }
+    }
+
+    /// Keeps the camera inside of `bounds`.
+    pub fn with_bounds(mut self, bounds: Rectangle) -> Camera {
+        self.bounds = Some(bounds);
+        self.clamp();
+        self
+    }
+
+    /// Centers the camera on `point`, as far as its bounds allow.
+    pub fn look_at(&mut self, point: Vec2) {
+        self.center = point;
+        self.clamp();
+    }
+
+    /// Changes the zoom, keeping the same point at the center of the screen.
+    /// Panics if `zoom` isn't strictly positive.
+    pub fn set_zoom(&mut self, zoom: f64) {
+        if zoom <= 0.0 {
+            panic!("Passed a non-positive zoom to Camera::set_zoom");
+        }
+
+        self.zoom = zoom;
+        self.clamp();
+    }
+
+    /// Moves the camera towards `target`, covering a larger part of the
+    /// distance left the higher `stiffness` is, so that the camera lags
+    /// smoothly behind what it follows. This doesn't depend on the rate at
+    /// which it is called.
+    pub fn follow(&mut self, target: Vec2, stiffness: f64, elapsed: f64) {
+        let t = 1.0 - (-stiffness * elapsed).exp();
+        self.center = self.center.lerp(target, t);
+        self.clamp();
+    }
+
+    /// The region of the world which is currently shown.
+    pub fn visible_region(&self) -> Rectangle {
+        let (w, h) = (self.screen_size.0 / self.zoom, self.screen_size.1 / self.zoom);
+
+        Rectangle {
+            x: self.center.x - w / 2.0,
+            y: self.center.y - h / 2.0,
+            w: w,
+            h: h,
+        }
+    }
+
+    /// Converts a point of the world to the screen.
+    pub fn to_screen(&self, point: Vec2) -> Vec2 {
+        let region = self.visible_region();
+        Vec2::new((point.x - region.x) * self.zoom, (point.y - region.y) * self.zoom)
+    }
+
+    /// Converts a point of the screen, such as the cursor, to the world.
+    pub fn to_world(&self, point: Vec2) -> Vec2 {
+        let region = self.visible_region();
+        Vec2::new(point.x / self.zoom + region.x, point.y / self.zoom + region.y)
+    }
+
+    /// Converts a rectangle of the world to the screen.
+    pub fn rect_to_screen(&self, rect: Rectangle) -> Rectangle {
+        let corner = self.to_screen(Vec2::new(rect.x, rect.y));
+
+        Rectangle {
+            x: corner.x,
+            y: corner.y,
+            w: rect.w * self.zoom,
+            h: rect.h * self.zoom,
+        }
+    }
+
+    /// Moves the camera back inside of its bounds. When the bounds are
+    /// smaller than what the camera shows, they are centered instead.
+    fn clamp(&mut self) {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let region = self.visible_region();
+        let center = bounds.center();
+
+        self.center.x =
+            if region.w >= bounds.w { center.x }
+            else { self.center.x.max(bounds.x + region.w / 2.0).min(bounds.x + bounds.w - region.w / 2.0) };
+
+        self.center.y =
+            if region.h >= bounds.h { center.y }
+            else { self.center.y.max(bounds.y + region.h / 2.0).min(bounds.y + bounds.h - region.h / 2.0) };
     }
 }
 
 pub trait CopySprite<T> {
-    fn copy_sprite(&mut self, sprite: &T, dest: Rectangle);
+    /// Draws `sprite` at `dest`, in screen coordinates.
+    fn copy_sprite(&mut self, sprite: &T, dest: Rectangle) {
+        self.copy_sprite_ex(sprite, dest, &DrawParams::new());
+    }
+
+    /// Same as `copy_sprite`, but transforms and colors the sprite according
+    /// to `params`.
+    fn copy_sprite_ex(&mut self, sprite: &T, dest: Rectangle, params: &DrawParams);
+
+    /// Draws `sprite` at `dest`, in the coordinates of the world seen through
+    /// `camera`. Nothing is drawn if `dest` is out of sight.
+    fn copy_sprite_world(&mut self, sprite: &T, camera: &Camera, dest: Rectangle) {
+        self.copy_sprite_world_ex(sprite, camera, dest, &DrawParams::new());
+    }
+
+    /// Same as `copy_sprite_world`, but transforms and colors the sprite
+    /// according to `params`, whose pivot is in world units.
+    fn copy_sprite_world_ex(&mut self, sprite: &T, camera: &Camera, dest: Rectangle, params: &DrawParams) {
+        // A rotated sprite can reach outside of its rectangle, up to the
+        // length of its diagonal.
+        let reach =
+            if params.angle == 0.0 { dest }
+            else { dest.expand(dest.w.max(dest.h) / 2.0) };
+
+        if camera.visible_region().overlaps(reach) {
+            let params = DrawParams {
+                pivot: params.pivot.map(|pivot| pivot * camera.zoom),
+                ..*params
+            };
+
+            self.copy_sprite_ex(sprite, camera.rect_to_screen(dest), &params);
+        }
+    }
 }
 
 impl<'window, T: Renderable> CopySprite<T> for Renderer<'window> {
-    fn copy_sprite(&mut self, renderable: &T, dest: Rectangle) {
-        renderable.render(self, dest);
+    fn copy_sprite_ex(&mut self, renderable: &T, dest: Rectangle, params: &DrawParams) {
+        renderable.render_ex(self, dest, params);
     }
 }
\ No newline at end of file
@@ -0,0 +1,226 @@
+use phi::{Error, Events, Phi, View, update_views, render_views};
+use phi::audio::Audio;
+use phi::data::Rectangle;
+use phi::stack::ViewStack;
+use phi::window::WindowConfig;
+use sdl2::pixels::{Color, PixelFormatEnum};
+
+/// Drives a `View` without a display or a GPU, which is what we want when
+/// running on a CI box.
+///
+/// SDL is told to use its dummy video and audio drivers, so that no display
+/// or sound card is needed. Every frame is drawn by the software renderer into
+/// a hidden window, whose content can be read back through `read_pixels` and
+/// `pixel`.
+///
+/// # Examples
+///
+/// ```
+/// let mut headless = Headless::new(800, 600, |phi| {
+///     Ok(Box::new(::views::main_menu::MainMenuView::new(phi)?))
+/// }).unwrap();
+///
+/// headless.run(10).unwrap();
+/// assert_eq!(headless.pixel(0, 0).unwrap(), Color::RGB(0, 0, 0));
+/// ```
+pub struct Headless {
+    pub phi: Phi<'static>,
+
+    /// The views being driven, empty once they asked the game to quit.
+    views: ViewStack,
+
+    // SDL must stay initialized for as long as we use the renderer.
+    _sdl_context: ::sdl2::Sdl,
+    _image_context: ::sdl2_image::Sdl2ImageContext,
+    _ttf_context: ::sdl2_ttf::Sdl2TtfContext,
+}
+
+impl Headless {
+    /// Initializes the underlying libraries with SDL's dummy drivers,
+    /// creates a hidden `width`x`height` window and starts with the view
+    /// returned by `init()`.
+    pub fn new<F>(width: u32, height: u32, init: F) -> Result<Headless, Error>
+        where F: Fn(&mut Phi) -> Result<Box<View>, Error> {
+
+        // This must happen before SDL is initialized, otherwise the drivers
+        // of the platform are picked.
+        ::sdl2::hint::set("SDL_VIDEODRIVER", "dummy");
+        ::sdl2::hint::set("SDL_AUDIODRIVER", "dummy");
+
+        let sdl_context = ::sdl2::init()?;
+        let video = sdl_context.video()?;
+        let image_context = ::sdl2_image::init(::sdl2_image::INIT_PNG)?;
+        let ttf_context = ::sdl2_ttf::init()
+            .map_err(|e| Error::Sdl(e.to_string()))?;
+
+        let config = WindowConfig::new("ArcadeRS Headless")
+            .size(width, height)
+            .resizable(false)
+            .hidden(true)
+            .accelerated(false);
+
+        let mut phi = Phi::new(
+            Events::new(sdl_context.event_pump()?, sdl_context.game_controller()?),
+            config.build(&video)?,
+            // Tests don't need sound, nor a working audio driver.
+            Audio::open(&sdl_context).unwrap_or_else(|_| Audio::disabled()),
+            config.screen()
+        )?;
+
+        // As in `spawn`, text is only typed once a view asks for it.
+        phi.stop_text_input()?;
+
+        let views = ViewStack::new(init(&mut phi)?);
+
+        Ok(Headless {
+            phi: phi,
+            views: views,
+            _sdl_context: sdl_context,
+            _image_context: image_context,
+            _ttf_context: ttf_context,
+        })
+    }
+
+    /// Runs a single update of the current view, lasting
+    /// `phi.tick_duration()`, then renders the stack. Views are driven by the
+    /// same functions as in `spawn`. Returns `Ok(false)` if the views asked
+    /// the game to quit, in which case every later call does nothing.
+    pub fn step(&mut self) -> Result<bool, Error> {
+        if self.views.is_empty() {
+            return Ok(false);
+        }
+
+        let dt = self.phi.tick_duration();
+
+        if !update_views(&mut self.phi, &mut self.views, dt)? {
+            return Ok(false);
+        }
+
+        render_views(&mut self.phi, &self.views, 0.0)?;
+        Ok(true)
+    }
+
+    /// Runs at most `frames` frames, stopping early if the view quits.
+    /// Returns the number of frames which were actually run.
+    pub fn run(&mut self, frames: u32) -> Result<u32, Error> {
+        for frame in 0..frames {
+            if !self.step()? {
+                return Ok(frame);
+            }
+        }
+
+        Ok(frames)
+    }
+
+    /// Whether the views asked the game to quit.
+    pub fn has_quit(&self) -> bool {
+        self.views.is_empty()
+    }
+
+    /// Returns the content of the framebuffer as tightly packed RGB24 rows,
+    /// from top to bottom.
+    pub fn read_pixels(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.phi.renderer.read_pixels(None, PixelFormatEnum::RGB24)?)
+    }
+
+    /// Returns the color of the pixel located at (x, y) in the framebuffer.
+    /// Returns `Error::InvalidRectangle` if it lies outside of it.
+    pub fn pixel(&self, x: u32, y: u32) -> Result<Color, Error> {
+        let (w, h) = self.phi.renderer.output_size()?;
+
+        if x >= w || y >= h {
+            return Err(Error::InvalidRectangle(Rectangle { x: x as f64, y: y as f64, w: 1.0, h: 1.0 }));
+        }
+
+        let pixels = self.read_pixels()?;
+        let i = ((y * w + x) * 3) as usize;
+
+        Ok(Color::RGB(pixels[i], pixels[i + 1], pixels[i + 2]))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use phi::{Phi, View, ViewAction};
+    use phi::text::TextStyle;
+    use phi::widgets::TextField;
+    use sdl2::event::Event;
+    use sdl2::keyboard::{self, Keycode};
+    use sdl2::pixels::Color;
+    use super::Headless;
+
+    /// Fills the screen with a single color.
+    struct Fill(Color);
+
+    impl View for Fill {
+        fn update(&mut self, _: &mut Phi, _: f64) -> ViewAction {
+            ViewAction::None
+        }
+
+        fn render(&self, phi: &mut Phi, _: f64) {
+            phi.renderer.set_draw_color(self.0);
+            phi.renderer.clear();
+        }
+    }
+
+    fn key_down(keycode: Keycode, repeat: bool) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            keymod: keyboard::NOMOD,
+            repeat: repeat,
+        }
+    }
+
+    // SDL can only be initialized once at a time, so every check which needs
+    // a `Headless` lives in this single test.
+    #[test]
+    fn runs_views_headlessly() {
+        let yellow = Color::RGB(255, 255, 0);
+        let mut headless = Headless::new(64, 48, |_| Ok(Box::new(Fill(yellow)))).unwrap();
+
+        reads_back_pixels(&mut headless, yellow);
+        edits_a_text_field(&mut headless);
+    }
+
+    fn reads_back_pixels(headless: &mut Headless, color: Color) {
+        assert_eq!(headless.run(3).unwrap(), 3);
+        assert!(!headless.has_quit());
+
+        assert_eq!(headless.pixel(0, 0).unwrap(), color);
+        assert_eq!(headless.pixel(63, 47).unwrap(), color);
+
+        assert!(headless.pixel(64, 0).is_err());
+        assert!(headless.pixel(0, 48).is_err());
+    }
+
+    fn edits_a_text_field(headless: &mut Headless) {
+        let mut field = TextField::new(TextStyle::new("assets/belligerent.ttf", 20, Color::RGB(255, 255, 255)), 5);
+
+        // Typing goes through the same path as the events sent by SDL.
+        headless.phi.events.inject(Event::TextInput { timestamp: 0, window_id: 0, text: "abcdefg".to_string() });
+        headless.phi.events.pump().unwrap();
+        assert!(!field.update(&headless.phi, 0.0));
+        assert_eq!(field.text(), "abcde");
+
+        // Holding backspace keeps erasing, once per repeat.
+        headless.phi.events.inject(key_down(Keycode::Backspace, false));
+        headless.phi.events.pump().unwrap();
+        field.update(&headless.phi, 0.0);
+        assert_eq!(field.text(), "abcd");
+
+        for _ in 0..2 {
+            headless.phi.events.inject(key_down(Keycode::Backspace, true));
+            headless.phi.events.pump().unwrap();
+            field.update(&headless.phi, 0.0);
+        }
+        assert_eq!(field.text(), "ab");
+
+        headless.phi.events.inject(key_down(Keycode::Return, false));
+        headless.phi.events.pump().unwrap();
+        assert!(field.update(&headless.phi, 0.0));
+    }
+}
@@ -0,0 +1,290 @@
+use phi::{Error, Events};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::Keycode;
+
+/// How far, between 0 and 1, an axis must be pushed before its bindings are
+/// considered active.
+pub const AXIS_DEADZONE: f64 = 0.3;
+
+/// The things which the player can ask the game to do, whatever the device
+/// used to ask for them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Confirm,
+    Back,
+    Pause,
+}
+
+impl Action {
+    /// Every action, in the order in which they are saved.
+    pub fn all() -> &'static [Action] {
+        static ALL: [Action; 8] = [
+            Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight,
+            Action::Fire, Action::Confirm, Action::Back, Action::Pause,
+        ];
+
+        &ALL
+    }
+
+    /// The name of the action in configuration files.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Fire => "fire",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+            Action::Pause => "pause",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all().iter().cloned().find(|action| action.name() == name)
+    }
+}
+
+/// A key, button or axis which triggers an action.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Key(Keycode),
+    Button(Button),
+
+    /// An axis pushed past `AXIS_DEADZONE`, towards its positive side if the
+    /// flag is set, and towards its negative side otherwise.
+    Axis(Axis, bool),
+}
+
+impl Binding {
+    /// Whether the binding is currently active.
+    pub fn is_held(&self, events: &Events) -> bool {
+        match *self {
+            Binding::Key(keycode) => events.keys_held.contains(&keycode),
+            Binding::Button(button) => events.buttons_held.contains(&button),
+            Binding::Axis(axis, positive) => axis_active(events.axis(axis), positive),
+        }
+    }
+
+    /// Whether the binding became active during the current frame.
+    pub fn is_pressed(&self, events: &Events) -> bool {
+        match *self {
+            Binding::Key(keycode) => events.now.keys_pressed.contains(&keycode),
+            Binding::Button(button) => events.now.buttons_pressed.contains(&button),
+            Binding::Axis(axis, positive) => match events.now.axes_moved.get(&axis) {
+                Some(&before) =>
+                    axis_active(events.axis(axis), positive) && !axis_active(before, positive),
+                None => false,
+            },
+        }
+    }
+
+    /// Parses a binding written as by `to_string`, for example `key:Space`,
+    /// `button:a` or `axis:lefty-`.
+    pub fn parse(text: &str) -> Option<Binding> {
+        let mut parts = text.splitn(2, ':');
+        let kind = parts.next().unwrap_or("").trim();
+        let name = parts.next().unwrap_or("").trim();
+
+        match kind {
+            "key" => Keycode::from_name(name).map(Binding::Key),
+            "button" => Button::from_string(name).map(Binding::Button),
+            "axis" => {
+                let (name, positive) =
+                    if name.ends_with('+') { (&name[..name.len() - 1], true) }
+                    else if name.ends_with('-') { (&name[..name.len() - 1], false) }
+                    else { return None };
+
+                Axis::from_string(name).map(|axis| Binding::Axis(axis, positive))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match *self {
+            Binding::Key(keycode) => format!("key:{}", keycode.name()),
+            Binding::Button(button) => format!("button:{}", button.string()),
+            Binding::Axis(axis, positive) =>
+                format!("axis:{}{}", axis.string(), if positive { '+' } else { '-' }),
+        }
+    }
+}
+
+fn axis_active(value: f64, positive: bool) -> bool {
+    if positive { value > AXIS_DEADZONE } else { value < -AXIS_DEADZONE }
+}
+
+/// Maps every action to the keys, buttons and axes which trigger it.
+///
+/// Bindings are saved as text, one action per line, followed by its bindings
+/// separated by commas:
+///
+/// ```text
+/// # Lines starting with a hash are ignored
+/// move_up = key:Up, key:W, button:dpup, axis:lefty-
+/// fire = key:Space, button:x
+/// ```
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Bindings {
+    /// Creates a mapping where no action has any binding.
+    pub fn empty() -> Bindings {
+        Bindings {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Adds a way to trigger `action`.
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_insert_with(Vec::new);
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes every way to trigger `action`.
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+
+    /// Returns the ways in which `action` can be triggered.
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(|b| &b[..]).unwrap_or(&[])
+    }
+
+    /// The name of the first way to trigger `action`, to show the player,
+    /// for example `Escape` or `start`.
+    pub fn label(&self, action: Action) -> String {
+        match self.get(action).first() {
+            Some(&Binding::Key(keycode)) => keycode.name(),
+            Some(&Binding::Button(button)) => button.string(),
+            Some(&Binding::Axis(axis, positive)) =>
+                format!("{}{}", axis.string(), if positive { '+' } else { '-' }),
+            None => "(unbound)".to_string(),
+        }
+    }
+
+    /// Whether any binding of `action` is currently active.
+    pub fn is_held(&self, action: Action, events: &Events) -> bool {
+        self.get(action).iter().any(|binding| binding.is_held(events))
+    }
+
+    /// Whether any binding of `action` became active during this frame.
+    pub fn is_pressed(&self, action: Action, events: &Events) -> bool {
+        self.get(action).iter().any(|binding| binding.is_pressed(events))
+    }
+
+    /// Reads the bindings saved at `path`. Actions which aren't listed in the
+    /// file have no binding.
+    pub fn load(path: &str) -> Result<Bindings, Error> {
+        let path = Path::new(path);
+        let mut text = String::new();
+
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => Error::AssetNotFound(path.to_path_buf()),
+                _ => Error::Io(path.to_path_buf(), e),
+            })?;
+
+        Bindings::parse(&text).map_err(|msg| Error::Decode(path.to_path_buf(), msg))
+    }
+
+    /// Writes the bindings to `path`, in a format which `load` understands.
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        let text = self.to_text();
+
+        File::create(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| Error::Io(Path::new(path).to_path_buf(), e))
+    }
+
+    /// Returns the bindings in the format of the files written by `save`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for action in Action::all() {
+            let bindings: Vec<String> = self.get(*action).iter()
+                .map(Binding::to_string)
+                .collect();
+
+            text.push_str(&format!("{} = {}\n", action.name(), bindings.join(", ")));
+        }
+
+        text
+    }
+
+    /// Reads bindings written as by `to_text`.
+    pub fn parse(text: &str) -> Result<Bindings, String> {
+        let mut bindings = Bindings::empty();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let action = Action::from_name(name)
+                .ok_or_else(|| format!("line {}: unknown action `{}`", number + 1, name))?;
+
+            for binding in parts.next().unwrap_or("").split(',') {
+                let binding = binding.trim();
+
+                if binding.is_empty() {
+                    continue;
+                }
+
+                let binding = Binding::parse(binding)
+                    .ok_or_else(|| format!("line {}: invalid binding `{}`", number + 1, binding))?;
+
+                bindings.bind(action, binding);
+            }
+        }
+
+        Ok(bindings)
+    }
+}
+
+impl Default for Bindings {
+    /// The arrows and the usual keys, along with a controller's directional
+    /// pad, left stick and face buttons.
+    fn default() -> Bindings {
+        let mut bindings = Bindings::empty();
+
+        let defaults = [
+            (Action::MoveUp, vec![Binding::Key(Keycode::Up), Binding::Button(Button::DPadUp), Binding::Axis(Axis::LeftY, false)]),
+            (Action::MoveDown, vec![Binding::Key(Keycode::Down), Binding::Button(Button::DPadDown), Binding::Axis(Axis::LeftY, true)]),
+            (Action::MoveLeft, vec![Binding::Key(Keycode::Left), Binding::Button(Button::DPadLeft), Binding::Axis(Axis::LeftX, false)]),
+            (Action::MoveRight, vec![Binding::Key(Keycode::Right), Binding::Button(Button::DPadRight), Binding::Axis(Axis::LeftX, true)]),
+            (Action::Fire, vec![Binding::Key(Keycode::Space), Binding::Button(Button::X)]),
+            (Action::Confirm, vec![Binding::Key(Keycode::Return), Binding::Button(Button::A), Binding::Button(Button::Start)]),
+            (Action::Back, vec![Binding::Key(Keycode::Escape), Binding::Button(Button::B)]),
+            (Action::Pause, vec![Binding::Key(Keycode::Escape), Binding::Button(Button::Start)]),
+        ];
+
+        for &(action, ref list) in defaults.iter() {
+            for binding in list {
+                bindings.bind(action, *binding);
+            }
+        }
+
+        bindings
+    }
+}
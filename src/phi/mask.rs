@@ -0,0 +1,227 @@
+use phi::Error;
+use phi::data::Rectangle;
+use std::path::Path;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+use sdl2_image::LoadSurface;
+
+/// The alpha, out of 255, above which a pixel is considered solid
+const ALPHA_THRESHOLD: u32 = 128;
+
+/// Tells which pixels of an image are solid, so that collisions can ignore
+/// the transparent corners of round sprites.
+///
+/// Masks are built from the image file, rather than from a `Sprite`, because
+/// the pixels of a texture can't be read back once it's uploaded.
+///
+/// # Examples
+///
+/// ```
+/// let masks = Mask::load("assets/asteroid.png", &frames)?;
+///
+/// // Rectangles are cheaper to compare, so they filter the pairs first.
+/// if ship_rect.overlaps(asteroid_rect) &&
+///    ship_mask.overlaps((ship_rect.x, ship_rect.y), &masks[frame], (asteroid_rect.x, asteroid_rect.y)) {
+///     // Boom
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Mask {
+    width: u32,
+    height: u32,
+
+    /// One bit per pixel, row by row, every row starting on a new word
+    bits: Vec<u64>,
+    words_per_row: usize,
+
+    /// The smallest rectangle containing every solid pixel
+    bounds: Option<Rectangle>,
+}
+
+impl Mask {
+    /// Reads the image located at `path`, and builds the mask of every one of
+    /// its `regions`, such as the frames of an animation.
+    pub fn load(path: &str, regions: &[Rectangle]) -> Result<Vec<Mask>, Error> {
+        let file = Path::new(path);
+
+        if !file.exists() {
+            return Err(Error::AssetNotFound(file.to_path_buf()));
+        }
+
+        let surface = Surface::from_file(file)
+            .map_err(|e| Error::Decode(file.to_path_buf(), e.to_string()))?;
+
+        regions.iter()
+            .map(|&region| Mask::from_surface(&surface, region)
+                .map_err(|msg| Error::Decode(file.to_path_buf(), msg)))
+            .collect()
+    }
+
+    /// Builds the mask of the `region` of `surface`, whatever its pixel
+    /// format, including paletted ones.
+    pub fn from_surface(surface: &Surface, region: Rectangle) -> Result<Mask, String> {
+        if region.x < 0.0 || region.y < 0.0 ||
+           region.x + region.w > surface.width() as f64 ||
+           region.y + region.h > surface.height() as f64 {
+            return Err(format!("region {:?} is outside of the image", region));
+        }
+
+        let (width, height) = (region.w as u32, region.h as u32);
+        let mut mask = Mask::empty(width, height);
+
+        // SDL refuses to create an empty surface.
+        if width == 0 || height == 0 {
+            return Ok(mask);
+        }
+
+        // The region is copied to a surface whose alpha is always the lowest
+        // byte of every pixel, which also resolves palettes and color keys.
+        // Its transparent background leaves the alpha of the image untouched.
+        let mut rgba = Surface::new(width, height, PixelFormatEnum::RGBA8888)?;
+        surface.blit(Some(region.to_sdl().map_err(|e| e.to_string())?), &mut rgba, None)?;
+        let pitch = rgba.pitch() as usize;
+
+        rgba.with_lock(|pixels| {
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let at = y * pitch + x * 4;
+                    let alpha = read_native(&pixels[at..at + 4]) & 0xff;
+
+                    if alpha >= ALPHA_THRESHOLD {
+                        mask.set(x as u32, y as u32);
+                    }
+                }
+            }
+        });
+
+        mask.bounds = mask.compute_bounds();
+        Ok(mask)
+    }
+
+    /// Creates a mask where every pixel is solid, for sprites which fill
+    /// their whole rectangle.
+    pub fn solid(width: u32, height: u32) -> Mask {
+        let mut mask = Mask::empty(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                mask.set(x, y);
+            }
+        }
+
+        mask.bounds = mask.compute_bounds();
+        mask
+    }
+
+    fn empty(width: u32, height: u32) -> Mask {
+        let words_per_row = (width as usize + 63) / 64;
+
+        Mask {
+            width: width,
+            height: height,
+            bits: vec![0; words_per_row * height as usize],
+            words_per_row: words_per_row,
+            bounds: None,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Whether the pixel at `(x, y)` is solid. Pixels outside of the mask
+    /// never are.
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return false;
+        }
+
+        let word = self.bits[y as usize * self.words_per_row + x as usize / 64];
+        word & (1 << (x % 64)) != 0
+    }
+
+    fn set(&mut self, x: u32, y: u32) {
+        self.bits[y as usize * self.words_per_row + x as usize / 64] |= 1 << (x % 64);
+    }
+
+    /// The smallest rectangle, relative to the top-left corner of the mask,
+    /// which contains every solid pixel. `None` if no pixel is solid.
+    pub fn bounds(&self) -> Option<Rectangle> {
+        self.bounds
+    }
+
+    fn compute_bounds(&self) -> Option<Rectangle> {
+        let (mut x0, mut y0, mut x1, mut y1) = (self.width, self.height, 0, 0);
+        let mut any = false;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x as i64, y as i64) {
+                    any = true;
+                    x0 = x0.min(x);
+                    y0 = y0.min(y);
+                    x1 = x1.max(x + 1);
+                    y1 = y1.max(y + 1);
+                }
+            }
+        }
+
+        if !any {
+            return None;
+        }
+
+        Some(Rectangle {
+            x: x0 as f64,
+            y: y0 as f64,
+            w: (x1 - x0) as f64,
+            h: (y1 - y0) as f64,
+        })
+    }
+
+    /// Whether a solid pixel of `self`, with its top-left corner at `pos`,
+    /// covers a solid pixel of `other`, with its top-left corner at
+    /// `other_pos`. Positions are rounded to the nearest pixel.
+    pub fn overlaps(&self, pos: (f64, f64), other: &Mask, other_pos: (f64, f64)) -> bool {
+        let (bounds, other_bounds) = match (self.bounds, other.bounds) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return false,
+        };
+
+        let (x, y) = (pos.0.round() as i64, pos.1.round() as i64);
+        let (ox, oy) = (other_pos.0.round() as i64, other_pos.1.round() as i64);
+
+        // Only the pixels where both bounding boxes meet need to be checked.
+        let left = (x + bounds.x as i64).max(ox + other_bounds.x as i64);
+        let top = (y + bounds.y as i64).max(oy + other_bounds.y as i64);
+        let right = (x + (bounds.x + bounds.w) as i64).min(ox + (other_bounds.x + other_bounds.w) as i64);
+        let bottom = (y + (bounds.y + bounds.h) as i64).min(oy + (other_bounds.y + other_bounds.h) as i64);
+
+        for sy in top..bottom {
+            for sx in left..right {
+                if self.get(sx - x, sy - y) && other.get(sx - ox, sy - oy) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// Reads a pixel of four bytes, stored in the byte order of the machine, as
+/// SDL does for packed formats.
+fn read_native(bytes: &[u8]) -> u32 {
+    let mut pixel = 0;
+
+    if cfg!(target_endian = "little") {
+        for &byte in bytes.iter().rev() {
+            pixel = (pixel << 8) | byte as u32;
+        }
+    } else {
+        for &byte in bytes {
+            pixel = (pixel << 8) | byte as u32;
+        }
+    }
+
+    pixel
+}
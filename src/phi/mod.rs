@@ -1,13 +1,42 @@
 #[macro_use]
 mod events;
+mod error;
+pub mod assets;
+pub mod atlas;
+pub mod audio;
+pub mod collision;
 pub mod data;
+pub mod ecs;
 pub mod gfx;
+pub mod headless;
+pub mod input;
+pub mod mask;
+pub mod replay;
+pub mod rng;
+pub mod stack;
+pub mod text;
+pub mod transition;
+pub mod widgets;
+pub mod window;
 
 use sdl2::render::Renderer;
+use sdl2::video::Window;
 use sdl2::pixels::Color;
 use std::path::Path;
 use std::collections::HashMap;
+use self::assets::AssetManager;
+use self::audio::Audio;
 use self::gfx::Sprite;
+use self::input::{Action, Bindings};
+use self::replay::{Player, Recorder};
+use self::rng::GameRng;
+use self::stack::ViewStack;
+use self::text::TextCache;
+use self::transition::Transition;
+use self::data::Rectangle;
+use self::window::{Fullscreen, Screen, WindowConfig};
+
+pub use self::error::Error;
 
 struct_events! {
     keyboard: {
@@ -17,7 +46,7 @@ struct_events! {
         key_left: Left,
         key_right: Right,
         key_space: Space,
-        key_enter: Enter
+        key_enter: Return
     },
 
     else: {
@@ -25,47 +54,318 @@ struct_events! {
     }
 }
 
+/// The number of times the game is updated every second, unless told otherwise
+/// through `Phi::set_tick_rate`.
+const DEFAULT_TICK_RATE: f64 = 60.0;
+
+/// The number of rendered strings which are kept around by `ttf_str_sprite`,
+/// unless told otherwise through `Phi::set_text_cache_capacity`.
+const DEFAULT_TEXT_CACHE_CAPACITY: usize = 256;
+
+/// The longest amount of time, in seconds, which is simulated between two
+/// frames. If rendering a frame takes longer than this, the game slows down
+/// instead of trying to catch up forever.
+const MAX_FRAME_TIME: f64 = 0.25;
+
 pub struct Phi<'window> {
     pub events: Events,
     pub renderer: Renderer<'window>,
+    pub audio: Audio,
+
+    /// The keys, buttons and axes which trigger every action
+    pub bindings: Bindings,
+
+    /// The number of fixed updates per second
+    tick_rate: f64,
+
+    /// The logical resolution in which the views draw
+    screen: Screen,
 
-    cached_fonts: HashMap<(&'static str, i32), ::sdl2_ttf::Font>,
+    /// The source of every random decision made by the game, so that a run
+    /// can be reproduced from its seed
+    pub rng: GameRng,
+
+    assets: AssetManager,
+    cached_fonts: HashMap<(String, i32), ::sdl2_ttf::Font>,
+    cached_text: TextCache,
 }
 
 impl<'window> Phi<'window> {
-    fn new(events: Events, renderer: Renderer<'window>) -> Phi<'window> {
-        Phi {
+    fn new(events: Events, renderer: Renderer<'window>, audio: Audio, screen: Screen) -> Result<Phi<'window>, Error> {
+        let mut phi = Phi {
             events: events,
             renderer: renderer,
+            audio: audio,
+            bindings: Bindings::default(),
+            tick_rate: DEFAULT_TICK_RATE,
+            screen: screen,
+            rng: GameRng::new(::rand::random::<u64>()),
+            assets: AssetManager::new(),
             cached_fonts: HashMap::new(),
+            cached_text: TextCache::new(DEFAULT_TEXT_CACHE_CAPACITY),
+        };
+
+        phi.fit_screen()?;
+        Ok(phi)
+    }
+
+    /// Sets the number of times `View::update` is called every second.
+    /// Panics if `tick_rate` isn't strictly positive.
+    pub fn set_tick_rate(&mut self, tick_rate: f64) {
+        if tick_rate <= 0.0 {
+            panic!("Passed a non-positive tick rate to Phi::set_tick_rate");
         }
+
+        self.tick_rate = tick_rate;
+    }
+
+    /// The amount of time, in seconds, simulated by every call to `View::update`.
+    pub fn tick_duration(&self) -> f64 {
+        1.0 / self.tick_rate
     }
 
+    /// The size of the logical screen in which the views draw, which doesn't
+    /// depend on the size of the window.
     pub fn output_size(&self) -> (f64, f64) {
-        let (w, h): (u32, u32) = self.renderer.output_size().unwrap();
-        (w as f64, h as f64)
+        self.screen.logical_size()
+    }
+
+    /// Returns the way in which the logical screen is mapped to the window.
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    /// Changes the resolution in which the views draw.
+    pub fn set_logical_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.screen.set_logical_size(width, height);
+        self.fit_screen()
+    }
+
+    /// Chooses whether the logical screen is only scaled by whole numbers,
+    /// which keeps pixel art crisp at the cost of wider bars.
+    pub fn set_integer_scale(&mut self, integer_scale: bool) -> Result<(), Error> {
+        self.screen.set_integer_scale(integer_scale);
+        self.fit_screen()
+    }
+
+    /// Fits the logical screen to the current size of the window, and has
+    /// the renderer and the cursor follow it.
+    fn fit_screen(&mut self) -> Result<(), Error> {
+        let (w, h): (u32, u32) = self.renderer.output_size()?;
+        self.screen.fit(w, h);
+        self.events.set_screen(self.screen);
+        self.apply_screen()
+    }
+
+    /// Scales what is drawn on the renderer to the logical screen.
+    fn apply_screen(&mut self) -> Result<(), Error> {
+        let scale = self.screen.scale();
+        let viewport = self.screen.viewport();
+        let (log_w, log_h) = self.screen.logical_size();
+
+        // The viewport is expressed in scaled pixels.
+        self.renderer.set_scale(scale as f32, scale as f32)?;
+        self.renderer.set_viewport(Rectangle {
+            x: viewport.x / scale,
+            y: viewport.y / scale,
+            w: log_w,
+            h: log_h,
+        }.to_sdl().ok());
+
+        Ok(())
+    }
+
+    /// Paints the parts of the window which the logical screen doesn't cover
+    /// in black, hiding whatever was drawn past its borders.
+    fn draw_bars(&mut self) -> Result<(), Error> {
+        let (w, h): (u32, u32) = self.renderer.output_size()?;
+        let bars = self.screen.bars(w, h);
+
+        if bars.is_empty() {
+            return Ok(());
+        }
+
+        self.renderer.set_scale(1.0, 1.0)?;
+        self.renderer.set_viewport(None);
+        self.renderer.set_draw_color(Color::RGB(0, 0, 0));
+
+        for bar in bars {
+            if let Ok(rect) = bar.to_sdl() {
+                self.renderer.fill_rect(rect);
+            }
+        }
+
+        self.apply_screen()
+    }
+
+    /// The seed from which `rng` was last reset.
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    /// Resets `rng`, so that it produces the sequence determined by `seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng.reseed(seed);
+    }
+
+    /// Starts saving the seed, then the input of every frame, to the replay
+    /// file at `path`.
+    pub fn start_recording(&mut self, path: &str) -> Result<(), Error> {
+        let recorder = Recorder::create(path, self.seed(), self.tick_rate, &self.bindings)?;
+        self.events.record(recorder);
+        Ok(())
+    }
+
+    /// Stops recording, and finishes writing the replay file.
+    pub fn stop_recording(&mut self) -> Result<(), Error> {
+        self.events.stop_recording()
+    }
+
+    /// Plays the replay file at `path`: the seed, tick rate and bindings are
+    /// restored, and the input of the player is replaced by the recorded one
+    /// until the replay is over.
+    pub fn start_replay(&mut self, path: &str) -> Result<(), Error> {
+        let player = Player::load(path)?;
+        self.set_seed(player.seed());
+        self.set_tick_rate(player.tick_rate());
+        self.bindings = player.bindings().clone();
+        self.events.replay(player);
+        Ok(())
+    }
+
+    /// Whether any binding of `action` is currently active.
+    pub fn action_held(&self, action: Action) -> bool {
+        self.bindings.is_held(action, &self.events)
+    }
+
+    /// Whether any binding of `action` became active during this frame.
+    pub fn action_pressed(&self, action: Action) -> bool {
+        self.bindings.is_pressed(action, &self.events)
+    }
+
+    /// Returns the window which the renderer draws into.
+    fn window(&self) -> Result<&Window, Error> {
+        self.renderer.window()
+            .ok_or_else(|| Error::Sdl("the renderer doesn't draw into a window".to_string()))
+    }
+
+    fn window_mut(&mut self) -> Result<&mut Window, Error> {
+        self.renderer.window_mut()
+            .ok_or_else(|| Error::Sdl("the renderer doesn't draw into a window".to_string()))
+    }
+
+    /// Starts sending the text typed by the player through
+    /// `events.now.text_input`, showing an on-screen keyboard if needed.
+    pub fn start_text_input(&mut self) -> Result<(), Error> {
+        self.window()?.subsystem().text_input().start();
+        Ok(())
+    }
+
+    /// Stops sending typed text, hiding the on-screen keyboard if needed.
+    pub fn stop_text_input(&mut self) -> Result<(), Error> {
+        self.window()?.subsystem().text_input().stop();
+        Ok(())
+    }
+
+    /// Whether typed text is being sent. Without a window, it never is.
+    pub fn is_text_input_active(&self) -> bool {
+        self.window()
+            .map(|window| window.subsystem().text_input().is_active())
+            .unwrap_or(false)
+    }
+
+    /// Returns the way in which the window currently covers the screen.
+    /// Without a window, this is `Fullscreen::Off`.
+    pub fn fullscreen(&self) -> Fullscreen {
+        self.window()
+            .map(|window| Fullscreen::from_sdl(window.fullscreen_state()))
+            .unwrap_or(Fullscreen::Off)
+    }
+
+    /// Changes the way in which the window covers the screen.
+    pub fn set_fullscreen(&mut self, fullscreen: Fullscreen) -> Result<(), Error> {
+        self.window_mut()?
+            .set_fullscreen(fullscreen.to_sdl())
+            .map_err(Error::Sdl)
+    }
+
+    /// Goes from a window to desktop fullscreen, or back.
+    pub fn toggle_fullscreen(&mut self) -> Result<(), Error> {
+        let fullscreen = match self.fullscreen() {
+            Fullscreen::Off => Fullscreen::Desktop,
+            Fullscreen::Exclusive | Fullscreen::Desktop => Fullscreen::Off,
+        };
+
+        self.set_fullscreen(fullscreen)
+    }
+
+    /// Resizes the window, when it isn't fullscreen.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.window_mut()?.set_size(width, height);
+        Ok(())
+    }
+
+    /// Returns a sprite showing the image located at `path`. Its texture is
+    /// shared with every other sprite loaded from the same path through this
+    /// method, and freed once none of them is alive anymore.
+    pub fn load_sprite(&mut self, path: &str) -> Result<Sprite, Error> {
+        self.assets.sprite(&self.renderer, path)
+    }
+
+    /// The amount of memory, in bytes, used by the textures loaded through
+    /// `load_sprite` which are still alive.
+    pub fn texture_memory(&self) -> usize {
+        self.assets.texture_memory()
+    }
+
+    /// Returns the font located at `font_path`, with the given size. Fonts are
+    /// cached, so that they are only loaded the first time they're requested.
+    pub fn font(&mut self, font_path: &str, size: i32) -> Result<&::sdl2_ttf::Font, Error> {
+        let key = (font_path.to_string(), size);
+
+        // First, we check if the font is already cached. If this is not the
+        // case, we start by trying to load the requested font.
+        if !self.cached_fonts.contains_key(&key) {
+            let path = Path::new(font_path);
+
+            if !path.exists() {
+                return Err(Error::AssetNotFound(path.to_path_buf()));
+            }
+
+            let font = ::sdl2_ttf::Font::from_file(path, size).map_err(Error::Font)?;
+
+            // If this worked, we cache the font we acquired.
+            self.cached_fonts.insert(key.clone(), font);
+        }
+
+        Ok(&self.cached_fonts[&key])
     }
 
     /// Renders a string of text as a sprite using the provided parameters.
-    pub fn ttf_str_sprite(&mut self, text: &str, font_path: &'static str, size: i32, color: Color) -> Option<Sprite> {
-        // First, we check if the font is already cached. If this is the case,
-        // we use it to render the text.
-        if let Some(font) = self.cached_fonts.get(&(font_path, size)) {
-            return font.render(text, ::sdl2_ttf::blended(color)).ok()
-                .and_then(|surface| self.renderer.create_texture_from_surface(&surface).ok())
-                .map(Sprite::new);
+    ///
+    /// The most recently used strings are cached, so that drawing the same
+    /// text on every frame doesn't upload a new texture every time.
+    pub fn ttf_str_sprite(&mut self, text: &str, font_path: &str, size: i32, color: Color) -> Result<Sprite, Error> {
+        if let Some(sprite) = self.cached_text.get(text, font_path, size, color) {
+            return Ok(sprite);
         }
 
-        // Otherwise, we start by trying to load the requested font.
-        ::sdl2_ttf::Font::from_file(Path::new(font_path), size).ok()
-            .and_then(|font| {
-                // If this worked, we cache the font we acquired.
-                self.cached_fonts.insert((font_path, size), font);
+        let surface = self.font(font_path, size)?
+            .render(text, ::sdl2_ttf::blended(color))
+            .map_err(|e| Error::Font(e.to_string()))?;
 
-                // Then, we call this method recursively. This avoids repeating
-                // the rendering code.
-                self.ttf_str_sprite(text, font_path, size, color)
-            })
+        let sprite = self.renderer.create_texture_from_surface(&surface)
+            .map(Sprite::new)
+            .map_err(|e| Error::Sdl(e.to_string()))?;
+
+        self.cached_text.insert(text, font_path, size, color, sprite.clone());
+        Ok(sprite)
+    }
+
+    /// Sets the number of rendered strings kept around by `ttf_str_sprite`.
+    /// The least recently used ones are dropped first.
+    pub fn set_text_cache_capacity(&mut self, capacity: usize) {
+        self.cached_text.set_capacity(capacity);
     }
 }
 
@@ -74,19 +374,55 @@ impl<'window> Phi<'window> {
 pub enum ViewAction {
     None,
     Quit,
+
+    /// Drops every view and replaces them with the given one.
     ChangeView(Box<View>),
+
+    /// Same as `ChangeView`, but goes from the current views to the new one
+    /// through the given `Transition`.
+    ChangeViewWith(Box<View>, Transition),
+
+    /// Puts a view on top of the current one, which is frozen until the new
+    /// view is popped.
+    PushView(Box<View>),
+
+    /// Drops the current view and resumes the one below it. The game quits if
+    /// there is no such view.
+    PopView,
+
+    /// Drops the current view and replaces it with the given one, leaving the
+    /// views below it untouched.
+    ReplaceView(Box<View>),
+
+    /// Stops the game because of an error, which is returned by `spawn`.
+    Fail(Error),
 }
 
 pub trait View {
-    /// Renders the current view and returns a `ViewAction` so it can communicate
-    /// with the render loop. Called on every frame.
-    // TODO: separate update and render logic?
-    fn render(&mut self, context: &mut Phi, elapsed: f64) -> ViewAction;
+    /// Updates the state of the current view and returns a `ViewAction` so it
+    /// can communicate with the game loop. Called at a fixed rate, with
+    /// `elapsed` always equal to `context.tick_duration()`.
+    fn update(&mut self, context: &mut Phi, elapsed: f64) -> ViewAction;
+
+    /// Draws the current state of the view. This should not modify the view,
+    /// so that frames can be skipped, or the simulation ran without drawing.
+    ///
+    /// `alpha`, between 0 and 1, tells how far we are between the last update
+    /// and the next one, so that motion can be interpolated.
+    fn render(&self, context: &mut Phi, alpha: f64);
+
+    /// Whether the view below this one in the stack should be rendered first,
+    /// for example because this is a menu drawn over a frozen game.
+    fn is_overlay(&self) -> bool {
+        false
+    }
 }
 
 
-/// Create a window with name `title`, initialize the underlying libraries and
-/// start the game with the view returned by `init()`.
+/// Create a window described by `config`, initialize the underlying libraries
+/// and start the game with the view returned by `init()`.
+///
+/// Returns once the game quits, or with the first error which stopped it.
 ///
 /// # Examples
 ///
@@ -96,61 +432,65 @@ pub trait View {
 /// ```
 /// struct MyView;
 /// impl View for MyView {
-///     fn render(&mut self, context: &mut Phi, _: f64) -> ViewAction {
+///     fn update(&mut self, context: &mut Phi, _: f64) -> ViewAction {
 ///         if context.events.now.quit {
 ///             return ViewAction::Quit;
 ///         }
 ///
+///         ViewAction::None
+///     }
+///
+///     fn render(&self, context: &mut Phi, _: f64) {
 ///         context.renderer.set_draw_color(Color::RGB(255, 255, 0));
 ///         context.renderer.clear();
-///         ViewAction::None
 ///     }
 /// }
 ///
-/// spawn("Example", |_| Box::new(MyView));
+/// spawn(WindowConfig::new("Example"), |_| Ok(Box::new(MyView))).unwrap();
 /// ```
-pub fn spawn<F>(title: &str, init: F)
-    where F: Fn(&mut Phi) -> Box<View> {
+pub fn spawn<F>(config: WindowConfig, init: F) -> Result<(), Error>
+    where F: Fn(&mut Phi) -> Result<Box<View>, Error> {
 
     // Initizalize SDL2
-    let sdl_context = ::sdl2::init().unwrap();
-    let video = sdl_context.video().unwrap();
-    let mut timer = sdl_context.timer().unwrap();
-    let _image_context = ::sdl2_image::init(::sdl2_image::INIT_PNG).unwrap();
-    let _ttf_context = ::sdl2_ttf::init().unwrap();
+    let sdl_context = ::sdl2::init()?;
+    let video = sdl_context.video()?;
+    let mut timer = sdl_context.timer()?;
+    let _image_context = ::sdl2_image::init(::sdl2_image::INIT_PNG)?;
+    let _ttf_context = ::sdl2_ttf::init()
+        .map_err(|e| Error::Sdl(e.to_string()))?;
 
-    // Create the window
-    let window = video.window("ArcadeRS Shooter", 800, 600)
-        .position_centered()
-        .opengl()
-        .resizable()
-        .build()
-        .unwrap();
+    // The game can be played without sound, so a missing audio device isn't
+    // fatal.
+    let audio = Audio::open(&sdl_context).unwrap_or_else(|error| {
+        eprintln!("Audio disabled: {}", error);
+        Audio::disabled()
+    });
 
+    // Create the window
     let mut context = Phi::new(
-        Events::new(sdl_context.event_pump().unwrap()),
-        window.renderer()
-            .accelerated()
-            .build().unwrap()
-    );
+        Events::new(sdl_context.event_pump()?, sdl_context.game_controller()?),
+        config.build(&video)?,
+        audio,
+        config.screen()
+    )?;
 
-    let mut current_view = init(&mut context);
+    // SDL starts sending typed text as soon as the video is initialized, but
+    // only the views which edit text should receive it.
+    context.stop_text_input()?;
+
+    let mut views = ViewStack::new(init(&mut context)?);
 
-    let interval = 1_000 / 60;
     let mut before = timer.ticks();
     let mut last_second = timer.ticks();
     let mut fps = 0u16;
 
-    loop {
-        let now = timer.ticks();
-        let dt = now - before;
-        let elapsed = dt as f64 / 1_000.0;
-
-        if dt < interval {
-            timer.delay(interval - dt);
-            continue;
-        }
+    // The amount of time, in seconds, which has passed but hasn't been
+    // simulated yet.
+    let mut lag = 0.0;
 
+    'running: loop {
+        let now = timer.ticks();
+        lag += ((now - before) as f64 / 1_000.0).min(MAX_FRAME_TIME);
         before = now;
         fps += 1;
 
@@ -160,12 +500,43 @@ pub fn spawn<F>(title: &str, init: F)
             fps = 0;
         }
 
-        context.events.pump(&mut context.renderer);
+        // Run as many fixed updates as needed to catch up with the real time.
+        let dt = context.tick_duration();
+        while lag >= dt {
+            if !update_views(&mut context, &mut views, dt)? {
+                break 'running;
+            }
 
-        match current_view.render(&mut context, 0.01) {
-            ViewAction::None => context.renderer.present(),
-            ViewAction::Quit => break,
-            ViewAction::ChangeView(new_view) => current_view = new_view,
+            lag -= dt;
         }
+
+        render_views(&mut context, &views, lag / dt)?;
+
+        // Give the CPU some rest between frames
+        timer.delay(1);
     }
+
+    context.stop_recording()
+}
+
+/// Pumps the events and updates the current view of the stack. Returns
+/// `Ok(false)` once the game should quit.
+///
+/// This is shared by `spawn` and `headless::Headless`, so that both drive the
+/// views in exactly the same way.
+fn update_views(context: &mut Phi, views: &mut ViewStack, elapsed: f64) -> Result<bool, Error> {
+    // The cursor is converted using the current size of the window.
+    context.fit_screen()?;
+    context.events.pump()?;
+    views.update(context, elapsed)
+}
+
+/// Renders the stack, `alpha` of the way between the last update and the next
+/// one, and shows the result on the screen.
+fn render_views(context: &mut Phi, views: &ViewStack, alpha: f64) -> Result<(), Error> {
+    context.fit_screen()?;
+    views.render(context, alpha);
+    context.draw_bars()?;
+    context.renderer.present();
+    Ok(())
 }
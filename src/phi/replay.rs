@@ -0,0 +1,501 @@
+use phi::Error;
+use phi::input::Bindings;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+
+/// The first bytes of every replay file
+const MAGIC: &'static [u8; 4] = b"PHIR";
+
+/// Incremented whenever the format changes, so that old replays are rejected
+/// instead of being played wrong.
+const VERSION: u8 = 4;
+
+/// The input which the game reacts to, stripped of everything which can't be
+/// replayed, such as timestamps. The controllers are told apart by their
+/// instance id, since several can hold the same button.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    KeyDown(Keycode),
+    KeyUp(Keycode),
+
+    /// A key held long enough for the system to repeat it
+    KeyRepeat(Keycode),
+
+    ButtonDown(i32, Button),
+    ButtonUp(i32, Button),
+
+    /// The raw value of an axis, as sent by SDL
+    AxisMotion(i32, Axis, i16),
+
+    /// The position of the cursor, which `Events` converts to logical pixels
+    /// before the input is recorded
+    MouseMotion(i32, i32),
+    MouseDown(MouseButton, i32, i32),
+    MouseUp(MouseButton, i32, i32),
+    MouseWheel(i32, i32),
+    Text(String),
+    Editing(String, i32),
+
+    /// One of the events listed in the `else` section of `struct_events!`,
+    /// identified by its alias
+    Flag(String),
+}
+
+impl Input {
+    /// Extracts the input carried by `event`, if the game cares about it.
+    /// The events handled through `Flag` aren't converted here.
+    pub fn from_event(event: Event) -> Option<Input> {
+        use sdl2::event::Event::*;
+
+        Some(match event {
+            KeyDown { keycode: Some(keycode), repeat: false, .. } => Input::KeyDown(keycode),
+            KeyDown { keycode: Some(keycode), repeat: true, .. } => Input::KeyRepeat(keycode),
+            KeyUp { keycode: Some(keycode), .. } => Input::KeyUp(keycode),
+            ControllerButtonDown { which, button, .. } => Input::ButtonDown(which, button),
+            ControllerButtonUp { which, button, .. } => Input::ButtonUp(which, button),
+            ControllerAxisMotion { which, axis, value, .. } => Input::AxisMotion(which, axis, value),
+            MouseMotion { x, y, .. } => Input::MouseMotion(x, y),
+            MouseButtonDown { mouse_btn, x, y, .. } => Input::MouseDown(mouse_btn, x, y),
+            MouseButtonUp { mouse_btn, x, y, .. } => Input::MouseUp(mouse_btn, x, y),
+            MouseWheel { x, y, .. } => Input::MouseWheel(x, y),
+            TextInput { text, .. } => Input::Text(text),
+            TextEditing { text, start, .. } => Input::Editing(text, start),
+            _ => return None,
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match *self {
+            Input::KeyDown(keycode) => { out.push(0); write_i32(out, keycode as i32); },
+            Input::KeyUp(keycode) => { out.push(1); write_i32(out, keycode as i32); },
+            Input::ButtonDown(which, button) => {
+                out.push(2);
+                write_i32(out, which);
+                write_str(out, &button.string());
+            },
+            Input::ButtonUp(which, button) => {
+                out.push(3);
+                write_i32(out, which);
+                write_str(out, &button.string());
+            },
+            Input::AxisMotion(which, axis, value) => {
+                out.push(4);
+                write_i32(out, which);
+                write_str(out, &axis.string());
+                write_i32(out, value as i32);
+            },
+            Input::MouseMotion(x, y) => { out.push(5); write_i32(out, x); write_i32(out, y); },
+            Input::MouseDown(button, x, y) => {
+                out.push(6);
+                out.push(mouse_button_id(button));
+                write_i32(out, x);
+                write_i32(out, y);
+            },
+            Input::MouseUp(button, x, y) => {
+                out.push(7);
+                out.push(mouse_button_id(button));
+                write_i32(out, x);
+                write_i32(out, y);
+            },
+            Input::MouseWheel(x, y) => { out.push(8); write_i32(out, x); write_i32(out, y); },
+            Input::Text(ref text) => { out.push(9); write_str(out, text); },
+            Input::Editing(ref text, start) => { out.push(10); write_str(out, text); write_i32(out, start); },
+            Input::Flag(ref alias) => { out.push(11); write_str(out, alias); },
+            Input::KeyRepeat(keycode) => { out.push(12); write_i32(out, keycode as i32); },
+        }
+    }
+
+    fn read(input: &mut Reader) -> Result<Input, String> {
+        Ok(match input.u8()? {
+            0 => Input::KeyDown(input.keycode()?),
+            1 => Input::KeyUp(input.keycode()?),
+            2 => Input::ButtonDown(input.i32()?, input.button()?),
+            3 => Input::ButtonUp(input.i32()?, input.button()?),
+            4 => {
+                let which = input.i32()?;
+                let name = input.string()?;
+                let axis = Axis::from_string(&name)
+                    .ok_or_else(|| format!("unknown axis `{}`", name))?;
+                Input::AxisMotion(which, axis, input.i32()? as i16)
+            },
+            5 => Input::MouseMotion(input.i32()?, input.i32()?),
+            6 => Input::MouseDown(input.mouse_button()?, input.i32()?, input.i32()?),
+            7 => Input::MouseUp(input.mouse_button()?, input.i32()?, input.i32()?),
+            8 => Input::MouseWheel(input.i32()?, input.i32()?),
+            9 => Input::Text(input.string()?),
+            10 => Input::Editing(input.string()?, input.i32()?),
+            11 => Input::Flag(input.string()?),
+            12 => Input::KeyRepeat(input.keycode()?),
+            tag => return Err(format!("unknown input tag {}", tag)),
+        })
+    }
+}
+
+/// Writes the input of every frame to a replay file, as the game runs.
+///
+/// A replay starts with the magic bytes `PHIR`, a version byte, the seed of
+/// the game's random number generator and the tick rate, which are both
+/// stored as little-endian `u64`s, then the bindings which the input is mapped
+/// through. Then, for every frame, comes the number of inputs as a `u16`,
+/// followed by the inputs themselves.
+///
+/// Every frame is written as soon as it is recorded, so that a crash doesn't
+/// lose the frames which led to it.
+pub struct Recorder {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &str, seed: u64, tick_rate: f64, bindings: &Bindings) -> Result<Recorder, Error> {
+        let path = PathBuf::from(path);
+        let file = File::create(&path)
+            .map_err(|e| Error::Io(path.clone(), e))?;
+
+        let mut recorder = Recorder {
+            path: path,
+            file: BufWriter::new(file),
+        };
+
+        let mut header = MAGIC.to_vec();
+        header.push(VERSION);
+        write_u64(&mut header, seed);
+        write_u64(&mut header, tick_rate.to_bits());
+        write_text(&mut header, &bindings.to_text());
+
+        recorder.write(&header)?;
+        Ok(recorder)
+    }
+
+    /// Appends the inputs of a single frame.
+    pub fn record(&mut self, frame: &[Input]) -> Result<(), Error> {
+        let mut bytes = Vec::new();
+        let len = frame.len().min(u16::max_value() as usize);
+        bytes.push(len as u8);
+        bytes.push((len >> 8) as u8);
+
+        for input in &frame[..len] {
+            input.write(&mut bytes);
+        }
+
+        self.write(&bytes)
+    }
+
+    /// Writes whatever is left, and closes the file. Dropping the recorder
+    /// does the same, but ignores the errors.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.file.flush()
+            .map_err(|e| Error::Io(self.path.clone(), e))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.file.write_all(bytes)
+            .and_then(|_| self.file.flush())
+            .map_err(|e| Error::Io(self.path.clone(), e))
+    }
+}
+
+/// Hands out the inputs saved by a `Recorder`, one frame at a time.
+pub struct Player {
+    seed: u64,
+    tick_rate: f64,
+    bindings: Bindings,
+    frames: VecDeque<Vec<Input>>,
+}
+
+impl Player {
+    /// Reads the whole replay located at `path`.
+    pub fn load(path: &str) -> Result<Player, Error> {
+        let path = Path::new(path);
+        let mut bytes = Vec::new();
+
+        File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => Error::AssetNotFound(path.to_path_buf()),
+                _ => Error::Io(path.to_path_buf(), e),
+            })?;
+
+        Player::parse(&bytes).map_err(|msg| Error::Decode(path.to_path_buf(), msg))
+    }
+
+    /// The seed of the random number generator when the replay was recorded.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The number of updates per second when the replay was recorded.
+    pub fn tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    /// The bindings through which the recorded input was mapped to actions.
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// The number of frames which haven't been played yet.
+    pub fn remaining(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns the inputs of the next frame, or `None` once the replay is over.
+    pub fn next_frame(&mut self) -> Option<Vec<Input>> {
+        self.frames.pop_front()
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Player, String> {
+        let mut input = Reader { bytes: bytes, pos: 0 };
+
+        if input.take(4)? != MAGIC {
+            return Err("not a replay".to_string());
+        }
+
+        let version = input.u8()?;
+        if version != VERSION {
+            return Err(format!("unsupported replay version {}", version));
+        }
+
+        let seed = input.u64()?;
+
+        let tick_rate = f64::from_bits(input.u64()?);
+        if !(tick_rate > 0.0) {
+            return Err(format!("invalid tick rate {}", tick_rate));
+        }
+
+        let bindings = Bindings::parse(&input.text()?)?;
+        let mut frames = VecDeque::new();
+
+        while !input.is_empty() {
+            let len = input.u16()? as usize;
+            let mut frame = Vec::with_capacity(len);
+
+            for _ in 0..len {
+                frame.push(Input::read(&mut input)?);
+            }
+
+            frames.push_back(frame);
+        }
+
+        Ok(Player {
+            seed: seed,
+            tick_rate: tick_rate,
+            bindings: bindings,
+            frames: frames,
+        })
+    }
+}
+
+
+fn write_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+fn write_u64(out: &mut Vec<u8>, value: u64) {
+    for i in 0..8 {
+        out.push((value >> (i * 8)) as u8);
+    }
+}
+
+/// Writes a string of at most 255 bytes, preceded by its length.
+fn write_str(out: &mut Vec<u8>, text: &str) {
+    let mut len = text.len().min(255);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    out.push(len as u8);
+    out.extend_from_slice(text[..len].as_bytes());
+}
+
+/// Writes a string of any length, preceded by its length as an `i32`.
+fn write_text(out: &mut Vec<u8>, text: &str) {
+    write_i32(out, text.len() as i32);
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn mouse_button_id(button: MouseButton) -> u8 {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+        MouseButton::X1 => 4,
+        MouseButton::X2 => 5,
+        _ => 0,
+    }
+}
+
+/// Reads the values written by the functions above.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.pos + len > self.bytes.len() {
+            return Err("unexpected end of replay".to_string());
+        }
+
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(b[0] as u16 | (b[1] as u16) << 8)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        let b = self.take(4)?;
+        Ok(b[0] as i32 | (b[1] as i32) << 8 | (b[2] as i32) << 16 | (b[3] as i32) << 24)
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(self.take(8)?.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64))
+    }
+
+    fn text(&mut self) -> Result<String, String> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Err(format!("invalid text length {}", len));
+        }
+
+        String::from_utf8(self.take(len as usize)?.to_vec()).map_err(|e| e.to_string())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u8()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|e| e.to_string())
+    }
+
+    fn keycode(&mut self) -> Result<Keycode, String> {
+        let id = self.i32()?;
+        Keycode::from_i32(id).ok_or_else(|| format!("unknown keycode {}", id))
+    }
+
+    fn button(&mut self) -> Result<Button, String> {
+        let name = self.string()?;
+        Button::from_string(&name).ok_or_else(|| format!("unknown button `{}`", name))
+    }
+
+    fn mouse_button(&mut self) -> Result<MouseButton, String> {
+        Ok(match self.u8()? {
+            1 => MouseButton::Left,
+            2 => MouseButton::Middle,
+            3 => MouseButton::Right,
+            4 => MouseButton::X1,
+            5 => MouseButton::X2,
+            id => return Err(format!("unknown mouse button {}", id)),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, Player, Recorder, MAGIC, VERSION};
+    use phi::input::Bindings;
+    use std::env;
+    use std::fs::File;
+    use std::io::Read;
+    use sdl2::controller::{Axis, Button};
+    use sdl2::keyboard::Keycode;
+    use sdl2::mouse::MouseButton;
+
+    /// Records `frames` into a temporary file, and returns its content.
+    fn record(name: &str, frames: &[Vec<Input>]) -> Vec<u8> {
+        let path = env::temp_dir().join(format!("phi-replay-{}", name));
+        let path = path.to_str().unwrap();
+
+        let mut recorder = Recorder::create(path, 0xdead_beef_1234, 75.0, &Bindings::default()).unwrap();
+        for frame in frames {
+            recorder.record(frame).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        let mut bytes = Vec::new();
+        File::open(path).unwrap().read_to_end(&mut bytes).unwrap();
+        ::std::fs::remove_file(path).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn plays_back_every_input() {
+        let frames = vec![
+            vec![
+                Input::KeyDown(Keycode::Space),
+                Input::KeyUp(Keycode::Escape),
+                Input::KeyRepeat(Keycode::Backspace),
+                Input::ButtonDown(0, Button::A),
+                Input::ButtonUp(-1, Button::DPadLeft),
+            ],
+            vec![],
+            vec![
+                Input::AxisMotion(3, Axis::LeftY, -32768),
+                Input::MouseMotion(-5, 480),
+                Input::MouseDown(MouseButton::Left, 10, 20),
+                Input::MouseUp(MouseButton::X2, 30, 40),
+                Input::MouseWheel(0, -1),
+                Input::Text("héllo".to_string()),
+                Input::Editing("ka".to_string(), 2),
+                Input::Flag("quit".to_string()),
+            ],
+        ];
+
+        let mut player = Player::parse(&record("every-input", &frames)).unwrap();
+        assert_eq!(player.seed(), 0xdead_beef_1234);
+        assert_eq!(player.tick_rate(), 75.0);
+        assert_eq!(player.bindings().to_text(), Bindings::default().to_text());
+        assert_eq!(player.remaining(), 3);
+
+        for frame in frames {
+            assert_eq!(player.next_frame(), Some(frame));
+        }
+        assert_eq!(player.next_frame(), None);
+    }
+
+    #[test]
+    fn rejects_truncated_replays() {
+        let frames = vec![vec![Input::KeyDown(Keycode::Space), Input::Text("abc".to_string())]];
+        let bytes = record("truncated", &frames);
+        let header = record("truncated-header", &[]).len();
+
+        assert!(Player::parse(&bytes).is_ok());
+        for len in 0..bytes.len() {
+            // Cutting the replay between two frames leaves a valid replay.
+            if len == header {
+                continue;
+            }
+            assert!(Player::parse(&bytes[..len]).is_err(), "accepted {} of {} bytes", len, bytes.len());
+        }
+    }
+
+    #[test]
+    fn rejects_other_files_and_versions() {
+        let mut bytes = record("header", &[vec![Input::KeyDown(Keycode::Space)]]);
+        assert_eq!(&bytes[..4], MAGIC);
+
+        bytes[4] = VERSION + 1;
+        assert!(Player::parse(&bytes).unwrap_err().contains("version"));
+
+        bytes[4] = VERSION;
+        bytes[0] = b'X';
+        assert_eq!(Player::parse(&bytes).unwrap_err(), "not a replay");
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+/// The random number generator of the game.
+///
+/// Everything random which affects gameplay should be drawn from it, so that
+/// a run can be reproduced from its seed alone. Randomness which doesn't
+/// matter, such as the speed of an animation, should be drawn from a named
+/// stream instead: every stream is seeded independently, so drawing from one
+/// of them doesn't shift the sequence produced by the others.
+///
+/// # Examples
+///
+/// ```
+/// let mut rng = GameRng::new(42);
+/// let speed = rng.gen::<f64>() * 100.0;
+/// let sparkle = rng.stream("cosmetic").gen::<f64>();
+/// ```
+pub struct GameRng {
+    seed: u64,
+    main: XorShiftRng,
+    streams: HashMap<String, XorShiftRng>,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        GameRng {
+            seed: seed,
+            main: xorshift(seed),
+            streams: HashMap::new(),
+        }
+    }
+
+    /// The seed from which the generator was last reset.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Resets the main sequence and every stream, as if the generator had
+    /// just been created from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = GameRng::new(seed);
+    }
+
+    /// Returns the stream called `name`, creating it on first use.
+    pub fn stream(&mut self, name: &str) -> &mut XorShiftRng {
+        let seed = self.seed;
+
+        self.streams.entry(name.to_string())
+            .or_insert_with(|| xorshift(seed ^ fnv1a(name)))
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.main.next_u32()
+    }
+}
+
+/// `XorShiftRng` can't be seeded with zeros only, hence the constant half.
+fn xorshift(seed: u64) -> XorShiftRng {
+    XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32, 0x9e37_79b9, 0x7f4a_7c15])
+}
+
+/// Hashes the name of a stream into the bits mixed with the seed. Unlike the
+/// hasher of the standard library, FNV-1a gives the same result across Rust
+/// versions, which keeps old seeds and replays meaningful.
+fn fnv1a(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
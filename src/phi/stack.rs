@@ -0,0 +1,94 @@
+use phi::{Error, Phi, View, ViewAction};
+use phi::transition::TransitionView;
+use std::mem;
+
+/// The views which are currently alive, the topmost one being the current
+/// view. Only the current view is updated, while the ones below it are frozen
+/// until they get back on top.
+pub struct ViewStack {
+    views: Vec<Box<View>>,
+}
+
+impl ViewStack {
+    /// Creates a stack holding a single view.
+    pub fn new(view: Box<View>) -> ViewStack {
+        ViewStack {
+            views: vec![view],
+        }
+    }
+
+    /// The number of views in the stack.
+    pub fn len(&self) -> usize {
+        self.views.len()
+    }
+
+    /// Whether the stack is empty, in which case there is nothing left to run.
+    pub fn is_empty(&self) -> bool {
+        self.views.is_empty()
+    }
+
+    /// Updates the current view and applies the `ViewAction` it returned.
+    /// Returns `Ok(false)` once the game should quit, that is, when asked to
+    /// or when the last view was popped, and the error if a view failed.
+    pub fn update(&mut self, context: &mut Phi, elapsed: f64) -> Result<bool, Error> {
+        let action = match self.views.last_mut() {
+            Some(view) => view.update(context, elapsed),
+            None => return Ok(false),
+        };
+
+        match action {
+            ViewAction::None => {},
+            ViewAction::Quit => self.views.clear(),
+
+            ViewAction::ChangeView(view) => {
+                self.views.clear();
+                self.views.push(view);
+            },
+
+            ViewAction::ChangeViewWith(view, transition) => {
+                // The transition takes over the views which were alive, so
+                // that they are still drawn while it plays.
+                let from = ViewStack {
+                    views: mem::replace(&mut self.views, Vec::new()),
+                };
+
+                self.views.push(Box::new(TransitionView::new(from, view, transition)));
+            },
+
+            ViewAction::PushView(view) => self.views.push(view),
+
+            ViewAction::PopView => {
+                self.views.pop();
+            },
+
+            ViewAction::ReplaceView(view) => {
+                self.views.pop();
+                self.views.push(view);
+            },
+
+            ViewAction::Fail(error) => {
+                self.views.clear();
+                return Err(error);
+            },
+        }
+
+        Ok(!self.views.is_empty())
+    }
+
+    /// Renders the current view, on top of the views it lets through.
+    pub fn render(&self, context: &mut Phi, alpha: f64) {
+        // We look for the highest view which hides everything below it, and
+        // draw from there on, in order.
+        let bottom = self.views.iter()
+            .rposition(|view| !view.is_overlay())
+            .unwrap_or(0);
+
+        let top = self.views.len().saturating_sub(1);
+
+        for (i, view) in self.views.iter().enumerate().skip(bottom) {
+            // Frozen views are not updated, so there is nothing to interpolate:
+            // we show them exactly as they were left.
+            view.render(context, if i == top { alpha } else { 1.0 });
+        }
+    }
+}
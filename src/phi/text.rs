@@ -0,0 +1,408 @@
+use phi::{Error, Phi};
+use phi::data::{Rectangle, Vec2};
+use phi::gfx::{CopySprite, DrawParams, Renderable, Sprite};
+use std::collections::HashMap;
+use sdl2::pixels::Color;
+use sdl2::render::Renderer;
+
+/// The way in which lines are placed horizontally, relative to each other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    /// Returns the horizontal offset at which something `inner_w` pixels wide
+    /// should be placed, inside of something `outer_w` pixels wide.
+    pub fn offset(&self, inner_w: f64, outer_w: f64) -> f64 {
+        match *self {
+            Align::Left => 0.0,
+            Align::Center => (outer_w - inner_w) / 2.0,
+            Align::Right => outer_w - inner_w,
+        }
+    }
+}
+
+/// The font, size and color in which text is rendered.
+#[derive(Clone, Debug)]
+pub struct TextStyle {
+    pub font_path: String,
+    pub size: i32,
+    pub color: Color,
+}
+
+impl TextStyle {
+    pub fn new(font_path: &str, size: i32, color: Color) -> TextStyle {
+        TextStyle {
+            font_path: font_path.to_string(),
+            size: size,
+            color: color,
+        }
+    }
+}
+
+/// Describes how a string should be split into lines, and how those lines
+/// should be placed. Newlines in the text always start a new line.
+///
+/// # Examples
+///
+/// ```
+/// let layout = TextLayout::new(TextStyle::new("assets/belligerent.ttf", 20, Color::RGB(255, 255, 255)))
+///     .max_width(300.0)
+///     .align(Align::Center);
+///
+/// let credits = layout.render(phi, "Made with Rust\nand a lot of coffee")?;
+/// phi.renderer.copy_sprite(&credits, Rectangle { x: 10.0, y: 10.0, w: credits.size().0, h: credits.size().1 });
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    pub style: TextStyle,
+    pub align: Align,
+
+    /// The width, in pixels, past which words are wrapped to the next line.
+    /// If `None`, lines are only broken on newlines.
+    pub max_width: Option<f64>,
+
+    /// The distance between the tops of two consecutive lines, relative to
+    /// the height of the font.
+    pub line_spacing: f64,
+}
+
+impl TextLayout {
+    /// Creates a layout of left-aligned lines, which are only broken on
+    /// newlines.
+    pub fn new(style: TextStyle) -> TextLayout {
+        TextLayout {
+            style: style,
+            align: Align::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+
+    pub fn align(mut self, align: Align) -> TextLayout {
+        self.align = align;
+        self
+    }
+
+    pub fn max_width(mut self, max_width: f64) -> TextLayout {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn line_spacing(mut self, line_spacing: f64) -> TextLayout {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    /// Returns the dimensions of the block which `text` would occupy, without
+    /// rendering it.
+    pub fn measure(&self, phi: &mut Phi, text: &str) -> Result<(f64, f64), Error> {
+        let lines = self.break_lines(phi, text)?;
+        let line_h = self.line_height(phi)?;
+        Ok(self.block_size(&lines, line_h))
+    }
+
+    /// Renders `text`, one sprite per line, placed according to the layout.
+    pub fn render(&self, phi: &mut Phi, text: &str) -> Result<TextBlock, Error> {
+        let lines = self.break_lines(phi, text)?;
+        let line_h = self.line_height(phi)?;
+        let (block_w, block_h) = self.block_size(&lines, line_h);
+        let mut sprites = Vec::with_capacity(lines.len());
+
+        for (i, &(ref line, line_w)) in lines.iter().enumerate() {
+            // SDL_ttf refuses to render empty strings, and there would be
+            // nothing to show anyway.
+            if line.is_empty() {
+                continue;
+            }
+
+            let sprite = phi.ttf_str_sprite(line, &self.style.font_path, self.style.size, self.style.color)?;
+            let (w, h) = sprite.size();
+
+            sprites.push((sprite, Rectangle {
+                x: self.align.offset(line_w, block_w),
+                y: line_h * i as f64,
+                w: w,
+                h: h,
+            }));
+        }
+
+        Ok(TextBlock {
+            lines: sprites,
+            size: (block_w, block_h),
+        })
+    }
+
+    /// The distance, in pixels, between the tops of two consecutive lines.
+    fn line_height(&self, phi: &mut Phi) -> Result<f64, Error> {
+        let font = phi.font(&self.style.font_path, self.style.size)?;
+        Ok(font.recommended_line_spacing() as f64 * self.line_spacing)
+    }
+
+    /// The dimensions of the block holding `lines`.
+    fn block_size(&self, lines: &[(String, f64)], line_h: f64) -> (f64, f64) {
+        let widest = lines.iter().fold(0.0, |acc: f64, &(_, w)| acc.max(w));
+        (self.max_width.unwrap_or(widest), line_h * lines.len() as f64)
+    }
+
+    /// Splits `text` into the lines which will be shown, along with their
+    /// width in pixels. Words which are wider than `max_width` by themselves
+    /// get their own line.
+    fn break_lines(&self, phi: &mut Phi, text: &str) -> Result<Vec<(String, f64)>, Error> {
+        let font = phi.font(&self.style.font_path, self.style.size)?;
+        let width_of = |s: &str| -> Result<f64, Error> {
+            font.size_of(s)
+                .map(|(w, _)| w as f64)
+                .map_err(|e| Error::Font(e.to_string()))
+        };
+
+        let mut lines = Vec::new();
+
+        for paragraph in text.split('\n') {
+            let max_width = match self.max_width {
+                Some(max_width) => max_width,
+                None => {
+                    lines.push((paragraph.to_string(), width_of(paragraph)?));
+                    continue;
+                }
+            };
+
+            let mut line = String::new();
+            let mut line_w = 0.0;
+
+            for word in paragraph.split_whitespace() {
+                let candidate =
+                    if line.is_empty() { word.to_string() }
+                    else { format!("{} {}", line, word) };
+
+                let candidate_w = width_of(&candidate)?;
+
+                // If the word doesn't fit on the current line, it starts the
+                // next one.
+                if candidate_w > max_width && !line.is_empty() {
+                    lines.push((line, line_w));
+                    line_w = width_of(word)?;
+                    line = word.to_string();
+                } else {
+                    line = candidate;
+                    line_w = candidate_w;
+                }
+            }
+
+            lines.push((line, line_w));
+        }
+
+        Ok(lines)
+    }
+}
+
+/// A block of text rendered through a `TextLayout`.
+pub struct TextBlock {
+    /// The sprite of every line, along with its position relative to the
+    /// top-left corner of the block
+    lines: Vec<(Sprite, Rectangle)>,
+    size: (f64, f64),
+}
+
+impl TextBlock {
+    /// Returns the dimensions of the block.
+    pub fn size(&self) -> (f64, f64) {
+        self.size
+    }
+}
+
+impl Renderable for TextBlock {
+    /// Renders every line, stretching the block to fit `dest`. The block is
+    /// flipped and rotated as a whole, rather than line by line.
+    fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, params: &DrawParams) {
+        let (w, h) = self.size;
+        let scale_x = if w > 0.0 { dest.w / w } else { 1.0 };
+        let scale_y = if h > 0.0 { dest.h / h } else { 1.0 };
+        let pivot = params.pivot.unwrap_or(Vec2::new(dest.w / 2.0, dest.h / 2.0));
+
+        for &(ref sprite, rect) in &self.lines {
+            let (line_w, line_h) = (rect.w * scale_x, rect.h * scale_y);
+            let mut offset = Vec2::new(rect.x * scale_x, rect.y * scale_y);
+
+            if params.flip_horizontal {
+                offset.x = dest.w - offset.x - line_w;
+            }
+
+            if params.flip_vertical {
+                offset.y = dest.h - offset.y - line_h;
+            }
+
+            renderer.copy_sprite_ex(sprite, Rectangle {
+                x: dest.x + offset.x,
+                y: dest.y + offset.y,
+                w: line_w,
+                h: line_h,
+            }, &DrawParams { pivot: Some(pivot - offset), ..*params });
+        }
+    }
+}
+
+
+/// The sprite of every character of a small alphabet, such as the digits,
+/// used to draw text which changes constantly, like a timer or a score.
+/// Unlike `ttf_str_sprite`, drawing a string never renders a new texture.
+///
+/// # Examples
+///
+/// ```
+/// let digits = Glyphs::new(phi, TextStyle::new("assets/belligerent.ttf", 24, Color::RGB(255, 255, 255)), "0123456789.")?;
+/// digits.render(phi, &format!("{:.1}", elapsed), 10.0, 10.0);
+/// ```
+pub struct Glyphs {
+    sprites: HashMap<char, Sprite>,
+}
+
+impl Glyphs {
+    /// Renders a sprite for every character of `alphabet`.
+    pub fn new(phi: &mut Phi, style: TextStyle, alphabet: &str) -> Result<Glyphs, Error> {
+        let mut sprites = HashMap::new();
+
+        for c in alphabet.chars() {
+            let surface = phi.font(&style.font_path, style.size)?
+                .render(&c.to_string(), ::sdl2_ttf::blended(style.color))
+                .map_err(|e| Error::Font(e.to_string()))?;
+
+            let sprite = phi.renderer.create_texture_from_surface(&surface)
+                .map(Sprite::new)
+                .map_err(|e| Error::Sdl(e.to_string()))?;
+
+            sprites.insert(c, sprite);
+        }
+
+        Ok(Glyphs { sprites: sprites })
+    }
+
+    /// Returns the dimensions of `text` once drawn. Characters outside of
+    /// the alphabet are skipped.
+    pub fn size_of(&self, text: &str) -> (f64, f64) {
+        text.chars()
+            .filter_map(|c| self.sprites.get(&c))
+            .fold((0.0, 0.0), |(w, h), sprite| {
+                let (glyph_w, glyph_h) = sprite.size();
+                (w + glyph_w, h.max(glyph_h))
+            })
+    }
+
+    /// Draws `text` with its top-left corner at (x, y), in screen coordinates.
+    /// Characters outside of the alphabet are skipped.
+    pub fn render(&self, phi: &mut Phi, text: &str, x: f64, y: f64) {
+        let mut x = x;
+
+        for sprite in text.chars().filter_map(|c| self.sprites.get(&c)) {
+            let (w, h) = sprite.size();
+            phi.renderer.copy_sprite(sprite, Rectangle { x: x, y: y, w: w, h: h });
+            x += w;
+        }
+    }
+}
+
+
+/// Identifies a string rendered in a given style.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TextKey {
+    text: String,
+    font_path: String,
+    size: i32,
+    color: (u8, u8, u8, u8),
+}
+
+/// Keeps the sprites of recently rendered strings, so that they don't need to
+/// be rendered and uploaded again. When full, the least recently used string
+/// is evicted.
+pub struct TextCache {
+    capacity: usize,
+
+    /// Every sprite, along with the last time it was requested
+    entries: HashMap<TextKey, (Sprite, u64)>,
+
+    /// Incremented on every access, this tells which entry is the oldest
+    clock: u64,
+}
+
+impl TextCache {
+    pub fn new(capacity: usize) -> TextCache {
+        TextCache {
+            capacity: capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// The number of strings in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Changes the number of strings which can be cached, evicting the least
+    /// recently used ones if there are too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+
+        while self.entries.len() > self.capacity {
+            self.evict();
+        }
+    }
+
+    /// Returns the sprite of `text` rendered with the given style, if cached.
+    pub fn get(&mut self, text: &str, font_path: &str, size: i32, color: Color) -> Option<Sprite> {
+        self.clock += 1;
+        let clock = self.clock;
+
+        self.entries.get_mut(&TextKey::new(text, font_path, size, color))
+            .map(|entry| {
+                entry.1 = clock;
+                entry.0.clone()
+            })
+    }
+
+    /// Caches the sprite of `text` rendered with the given style.
+    pub fn insert(&mut self, text: &str, font_path: &str, size: i32, color: Color, sprite: Sprite) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = TextKey::new(text, font_path, size, color);
+
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.evict();
+        }
+
+        self.clock += 1;
+        self.entries.insert(key, (sprite, self.clock));
+    }
+
+    /// Drops the least recently used string. The cache is small enough for
+    /// a linear search to be cheaper than maintaining a second index.
+    fn evict(&mut self) {
+        let oldest = self.entries.iter()
+            .min_by_key(|&(_, &(_, last_used))| last_used)
+            .map(|(key, _)| key.clone());
+
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+impl TextKey {
+    fn new(text: &str, font_path: &str, size: i32, color: Color) -> TextKey {
+        TextKey {
+            text: text.to_string(),
+            font_path: font_path.to_string(),
+            size: size,
+            color: color.rgba(),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use phi::{Error, Phi, View, ViewAction};
+use phi::data::Rectangle;
+use phi::stack::ViewStack;
+use std::cell::{Cell, RefCell};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{BlendMode, Texture};
+
+/// The direction in which a `Transition` moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// The ways in which we can go from a view to the next one. Every transition
+/// holds its duration, in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    /// The old view fades to black, then the new one fades in.
+    FadeToBlack(f64),
+
+    /// The new view progressively appears over the old one.
+    Crossfade(f64),
+
+    /// The new view pushes the old one out of the screen.
+    Slide(Direction, f64),
+
+    /// The new view is uncovered over the old one, as if a curtain was pulled.
+    Wipe(Direction, f64),
+}
+
+impl Transition {
+    /// The time it takes for the transition to complete, in seconds.
+    pub fn duration(&self) -> f64 {
+        match *self {
+            Transition::FadeToBlack(duration) |
+            Transition::Crossfade(duration) |
+            Transition::Slide(_, duration) |
+            Transition::Wipe(_, duration) => duration,
+        }
+    }
+}
+
+/// Plays a `Transition` between the views which were on the stack and the
+/// view which replaces them. Both stay alive, but frozen, while the transition
+/// plays; once it is over, the new view takes its place.
+pub struct TransitionView {
+    from: ViewStack,
+    to: Option<Box<View>>,
+    transition: Transition,
+
+    /// The time, in seconds, since the transition started
+    elapsed: f64,
+
+    /// The textures which the views are drawn into, when the transition needs
+    /// to move or blend them. They are kept around to avoid creating new ones
+    /// on every frame.
+    targets: RefCell<Option<(Texture, Texture)>>,
+
+    /// Set once drawing into the textures failed, for example because the
+    /// renderer doesn't support it. Every transition then fades to black.
+    targets_failed: Cell<bool>,
+}
+
+impl TransitionView {
+    pub fn new(from: ViewStack, to: Box<View>, transition: Transition) -> TransitionView {
+        TransitionView {
+            from: from,
+            to: Some(to),
+            transition: transition,
+            elapsed: 0.0,
+            targets: RefCell::new(None),
+            targets_failed: Cell::new(false),
+        }
+    }
+
+    /// Returns the textures in which the views can be drawn, creating them if
+    /// they are missing or don't match the size of the screen anymore.
+    ///
+    /// The textures have the size of the logical screen, which the renderer
+    /// draws into without any scaling.
+    fn take_targets(&self, phi: &mut Phi) -> Result<(Texture, Texture), Error> {
+        let (w, h) = phi.output_size();
+        let (w, h) = (w.round() as u32, h.round() as u32);
+
+        if let Some((from_tex, to_tex)) = self.targets.borrow_mut().take() {
+            let query = from_tex.query();
+            if query.width == w && query.height == h {
+                return Ok((from_tex, to_tex));
+            }
+        }
+
+        let create = || phi.renderer.create_texture_target(PixelFormatEnum::RGBA8888, w, h)
+            .map_err(|e| Error::Sdl(e.to_string()));
+
+        Ok((create()?, create()?))
+    }
+
+    /// Renders the old views fading out, then the new one fading in.
+    fn fade_to_black(&self, phi: &mut Phi, to: &View, progress: f64, screen: Rectangle) {
+        // During the first half, we fade the old views out; during the
+        // second half, we fade the new one in.
+        let darkness =
+            if progress < 0.5 {
+                self.from.render(phi, 1.0);
+                progress * 2.0
+            } else {
+                to.render(phi, 1.0);
+                (1.0 - progress) * 2.0
+            };
+
+        phi.renderer.set_blend_mode(BlendMode::Blend);
+        phi.renderer.set_draw_color(Color::RGBA(0, 0, 0, (darkness * 255.0) as u8));
+        if let Ok(rect) = screen.to_sdl() {
+            phi.renderer.fill_rect(rect);
+        }
+        phi.renderer.set_blend_mode(BlendMode::None);
+    }
+
+    /// Renders the transitions which draw the views into textures first, to
+    /// move or blend them.
+    fn render_through_targets(&self, phi: &mut Phi, to: &View, progress: f64, screen: Rectangle) -> Result<(), Error> {
+        let (w, h) = (screen.w, screen.h);
+
+        match self.transition {
+            Transition::FadeToBlack(_) => self.fade_to_black(phi, to, progress, screen),
+
+            Transition::Crossfade(_) => {
+                let (from_tex, mut to_tex) = self.take_targets(phi)?;
+                to_tex = render_to_texture(phi, to_tex, |phi| to.render(phi, 1.0))?;
+
+                self.from.render(phi, 1.0);
+                to_tex.set_blend_mode(BlendMode::Blend);
+                to_tex.set_alpha_mod((progress * 255.0) as u8);
+                phi.renderer.copy(&to_tex, None, screen.to_sdl().ok());
+
+                *self.targets.borrow_mut() = Some((from_tex, to_tex));
+            },
+
+            Transition::Slide(direction, _) => {
+                let (mut from_tex, mut to_tex) = self.take_targets(phi)?;
+                from_tex = render_to_texture(phi, from_tex, |phi| self.from.render(phi, 1.0))?;
+                to_tex = render_to_texture(phi, to_tex, |phi| to.render(phi, 1.0))?;
+
+                // The old views leave the screen in `direction`, while the new
+                // one enters it from the opposite side.
+                let (dx, dy) = match direction {
+                    Direction::Left => (-w, 0.0),
+                    Direction::Right => (w, 0.0),
+                    Direction::Up => (0.0, -h),
+                    Direction::Down => (0.0, h),
+                };
+
+                phi.renderer.copy(&from_tex, None, Rectangle {
+                    x: dx * progress,
+                    y: dy * progress,
+                    ..screen
+                }.to_sdl().ok());
+
+                phi.renderer.copy(&to_tex, None, Rectangle {
+                    x: dx * (progress - 1.0),
+                    y: dy * (progress - 1.0),
+                    ..screen
+                }.to_sdl().ok());
+
+                *self.targets.borrow_mut() = Some((from_tex, to_tex));
+            },
+
+            Transition::Wipe(direction, _) => {
+                let (from_tex, mut to_tex) = self.take_targets(phi)?;
+                to_tex = render_to_texture(phi, to_tex, |phi| to.render(phi, 1.0))?;
+
+                // The uncovered part of the new view grows in `direction`.
+                let uncovered = match direction {
+                    Direction::Right => Rectangle { w: w * progress, ..screen },
+                    Direction::Left => Rectangle { x: w * (1.0 - progress), w: w * progress, ..screen },
+                    Direction::Down => Rectangle { h: h * progress, ..screen },
+                    Direction::Up => Rectangle { y: h * (1.0 - progress), h: h * progress, ..screen },
+                };
+
+                self.from.render(phi, 1.0);
+
+                // A region which doesn't cover any pixel can't be converted,
+                // in which case there is nothing to show yet.
+                if let Ok(rect) = uncovered.to_sdl() {
+                    phi.renderer.copy(&to_tex, Some(rect), Some(rect));
+                }
+
+                *self.targets.borrow_mut() = Some((from_tex, to_tex));
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl View for TransitionView {
+    fn update(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        self.elapsed += elapsed;
+
+        if self.elapsed >= self.transition.duration() {
+            // `to` is only taken once, after which the stack drops us.
+            if let Some(to) = self.to.take() {
+                return ViewAction::ChangeView(to);
+            }
+        }
+
+        ViewAction::None
+    }
+
+    fn render(&self, phi: &mut Phi, alpha: f64) {
+        let to = match self.to {
+            Some(ref to) => to,
+            None => return,
+        };
+
+        // How far we are in the transition, between 0 and 1
+        let duration = self.transition.duration();
+        let progress =
+            if duration <= 0.0 { 1.0 }
+            else { ((self.elapsed + alpha * phi.tick_duration()) / duration).min(1.0) };
+
+        let (w, h) = phi.output_size();
+        let screen = Rectangle { x: 0.0, y: 0.0, w: w, h: h };
+
+        // Without render targets, the transition falls back to fading
+        // through black, which draws the views directly on the screen.
+        if !self.targets_failed.get() {
+            if self.render_through_targets(phi, &**to, progress, screen).is_ok() {
+                return;
+            }
+
+            self.targets_failed.set(true);
+        }
+
+        self.fade_to_black(phi, &**to, progress, screen);
+    }
+}
+
+/// Draws into `texture` using `draw`, instead of drawing on the screen, and
+/// returns the texture once it's done.
+fn render_to_texture<F>(phi: &mut Phi, texture: Texture, draw: F) -> Result<Texture, Error>
+    where F: FnOnce(&mut Phi) {
+
+    phi.renderer.render_target()
+        .ok_or_else(|| Error::Sdl("the renderer can't draw into textures".to_string()))?
+        .set(texture)?;
+
+    // The texture already has the size of the logical screen. Failing to
+    // change the scale only spoils a frame of the transition, so it isn't
+    // worth stopping the game for.
+    let _ = phi.renderer.set_scale(1.0, 1.0);
+    phi.renderer.set_viewport(None);
+    draw(phi);
+
+    let texture = phi.renderer.render_target()
+        .ok_or_else(|| Error::Sdl("the renderer can't draw into textures".to_string()))?
+        .reset()?;
+    let _ = phi.apply_screen();
+
+    texture.ok_or_else(|| Error::Sdl("the texture was lost while drawing into it".to_string()))
+}
@@ -0,0 +1,158 @@
+use phi::{Error, Phi};
+use phi::data::Rectangle;
+use phi::gfx::CopySprite;
+use phi::text::TextStyle;
+use sdl2::keyboard::Keycode;
+
+/// The number of times per second the cursor of a text field blinks
+const CURSOR_BLINK_RATE: f64 = 2.0;
+
+/// A single line of editable text, for example to enter the player's name.
+///
+/// The field only receives text while text input is active, which is done
+/// through `Phi::start_text_input`.
+///
+/// # Examples
+///
+/// ```
+/// let mut name = TextField::new(TextStyle::new("assets/belligerent.ttf", 32, Color::RGB(255, 255, 255)), 3);
+/// phi.start_text_input()?;
+///
+/// // In `View::update`
+/// if name.update(phi, elapsed) {
+///     phi.stop_text_input()?;
+///     save_high_score(name.text());
+/// }
+///
+/// // In `View::render`
+/// name.render(phi, 100.0, 100.0)?;
+/// ```
+pub struct TextField {
+    pub style: TextStyle,
+
+    /// The longest text which can be entered, in characters
+    max_len: usize,
+
+    text: String,
+
+    /// The position of the cursor, as a byte offset in `text`
+    cursor: usize,
+
+    /// The time since the cursor last moved, used to make it blink
+    elapsed: f64,
+}
+
+impl TextField {
+    pub fn new(style: TextStyle, max_len: usize) -> TextField {
+        TextField {
+            style: style,
+            max_len: max_len,
+            text: String::new(),
+            cursor: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the content of the field, truncated to its length limit, and
+    /// moves the cursor to its end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().take(self.max_len).collect();
+        self.cursor = self.text.len();
+        self.elapsed = 0.0;
+    }
+
+    /// Applies the text typed, and the editing keys pressed, during this
+    /// frame. Holding an editing key repeats it, as in any other text field.
+    /// Returns whether the player pressed enter to submit the field.
+    pub fn update(&mut self, phi: &Phi, elapsed: f64) -> bool {
+        self.elapsed += elapsed;
+
+        for c in phi.events.now.text_input.chars() {
+            if self.text.chars().count() >= self.max_len {
+                break;
+            }
+
+            self.text.insert(self.cursor, c);
+            self.cursor += c.len_utf8();
+            self.elapsed = 0.0;
+        }
+
+        let pressed = &phi.events.now.keys_pressed;
+        let typed = |keycode: Keycode|
+            pressed.contains(&keycode) || phi.events.now.keys_repeated.contains(&keycode);
+
+        if typed(Keycode::Backspace) {
+            if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                self.cursor -= c.len_utf8();
+                self.text.remove(self.cursor);
+                self.elapsed = 0.0;
+            }
+        }
+
+        if typed(Keycode::Delete) && self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+            self.elapsed = 0.0;
+        }
+
+        if typed(Keycode::Left) {
+            if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                self.cursor -= c.len_utf8();
+                self.elapsed = 0.0;
+            }
+        }
+
+        if typed(Keycode::Right) {
+            if let Some(c) = self.text[self.cursor..].chars().next() {
+                self.cursor += c.len_utf8();
+                self.elapsed = 0.0;
+            }
+        }
+
+        if pressed.contains(&Keycode::Home) {
+            self.cursor = 0;
+            self.elapsed = 0.0;
+        }
+
+        if pressed.contains(&Keycode::End) {
+            self.cursor = self.text.len();
+            self.elapsed = 0.0;
+        }
+
+        pressed.contains(&Keycode::Return) || pressed.contains(&Keycode::KpEnter)
+    }
+
+    /// Draws the text with its top-left corner at `(x, y)`, followed by a
+    /// blinking cursor.
+    pub fn render(&self, phi: &mut Phi, x: f64, y: f64) -> Result<(), Error> {
+        let (cursor_x, line_h) = {
+            let font = phi.font(&self.style.font_path, self.style.size)?;
+            let (w, _) = font.size_of(&self.text[..self.cursor])
+                .map_err(|e| Error::Font(e.to_string()))?;
+
+            (w as f64, font.height() as f64)
+        };
+
+        // SDL_ttf refuses to render empty strings.
+        if !self.text.is_empty() {
+            let sprite = phi.ttf_str_sprite(&self.text, &self.style.font_path, self.style.size, self.style.color)?;
+            let (w, h) = sprite.size();
+            phi.renderer.copy_sprite(&sprite, Rectangle { x: x, y: y, w: w, h: h });
+        }
+
+        // The cursor is shown during the first half of every blink.
+        if (self.elapsed * CURSOR_BLINK_RATE).fract() < 0.5 {
+            phi.renderer.set_draw_color(self.style.color);
+            let cursor = Rectangle { x: x + cursor_x, y: y, w: 2.0, h: line_h };
+
+            if let Ok(rect) = cursor.to_sdl() {
+                phi.renderer.fill_rect(rect);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+use phi::Error;
+use phi::data::Rectangle;
+use std::path::Path;
+use sdl2::VideoSubsystem;
+use sdl2::render::Renderer;
+use sdl2::surface::Surface;
+use sdl2::video::FullscreenType;
+use sdl2_image::LoadSurface;
+
+/// The ways in which the window can cover the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fullscreen {
+    /// A regular window
+    Off,
+
+    /// Changes the video mode of the display to fit the window
+    Exclusive,
+
+    /// A window which covers the desktop, without changing the video mode
+    Desktop,
+}
+
+impl Fullscreen {
+    pub fn to_sdl(&self) -> FullscreenType {
+        match *self {
+            Fullscreen::Off => FullscreenType::Off,
+            Fullscreen::Exclusive => FullscreenType::True,
+            Fullscreen::Desktop => FullscreenType::Desktop,
+        }
+    }
+
+    pub fn from_sdl(sdl: FullscreenType) -> Fullscreen {
+        match sdl {
+            FullscreenType::Off => Fullscreen::Off,
+            FullscreenType::True => Fullscreen::Exclusive,
+            FullscreenType::Desktop => Fullscreen::Desktop,
+        }
+    }
+}
+
+/// Maps the logical resolution, in which every view draws, to the pixels of
+/// the window, so that resizing the window scales the game instead of showing
+/// more of it.
+///
+/// The logical screen keeps its aspect ratio: it is centered in the window,
+/// with black bars on the sides which it doesn't cover.
+///
+/// # Examples
+///
+/// ```
+/// let mut screen = Screen::new(400, 300, false);
+/// screen.fit(1000, 600);
+/// assert_eq!(screen.scale(), 2.0);
+/// assert_eq!(screen.viewport(), Rectangle { x: 100.0, y: 0.0, w: 800.0, h: 600.0 });
+/// assert_eq!(screen.to_logical((500.0, 300.0)), (200.0, 150.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Screen {
+    logical_size: (f64, f64),
+
+    /// Whether the logical pixels should only be scaled by whole numbers, so
+    /// that pixel art stays crisp
+    integer_scale: bool,
+
+    /// The number of window pixels covered by every logical pixel
+    scale: f64,
+
+    /// The region of the window which the logical screen covers, in pixels
+    viewport: Rectangle,
+}
+
+impl Screen {
+    /// Creates a `width`x`height` logical screen, which covers a window of
+    /// the same size until it is told otherwise through `fit`.
+    pub fn new(width: u32, height: u32, integer_scale: bool) -> Screen {
+        let (w, h) = (width as f64, height as f64);
+
+        Screen {
+            logical_size: (w, h),
+            integer_scale: integer_scale,
+            scale: 1.0,
+            viewport: Rectangle { x: 0.0, y: 0.0, w: w, h: h },
+        }
+    }
+
+    pub fn logical_size(&self) -> (f64, f64) {
+        self.logical_size
+    }
+
+    pub fn set_logical_size(&mut self, width: u32, height: u32) {
+        self.logical_size = (width as f64, height as f64);
+    }
+
+    pub fn integer_scale(&self) -> bool {
+        self.integer_scale
+    }
+
+    pub fn set_integer_scale(&mut self, integer_scale: bool) {
+        self.integer_scale = integer_scale;
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn viewport(&self) -> Rectangle {
+        self.viewport
+    }
+
+    /// Finds the largest region of a `width`x`height` window which the
+    /// logical screen can cover.
+    pub fn fit(&mut self, width: u32, height: u32) {
+        let (out_w, out_h) = (width as f64, height as f64);
+        let (log_w, log_h) = self.logical_size;
+        let mut scale = (out_w / log_w).min(out_h / log_h);
+
+        // When the window is smaller than the logical screen, shrinking it by
+        // a whole number would make it vanish, so we shrink it smoothly.
+        if self.integer_scale && scale >= 1.0 {
+            scale = scale.floor();
+        }
+
+        let (w, h) = (log_w * scale, log_h * scale);
+
+        self.scale = scale;
+        self.viewport = Rectangle {
+            x: ((out_w - w) / 2.0).floor(),
+            y: ((out_h - h) / 2.0).floor(),
+            w: w,
+            h: h,
+        };
+    }
+
+    /// Converts a position in the window, such as the one of the cursor, to
+    /// the logical screen.
+    pub fn to_logical(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        ((x - self.viewport.x) / self.scale, (y - self.viewport.y) / self.scale)
+    }
+
+    /// Converts a position on the logical screen to the window.
+    pub fn to_window(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (x * self.scale + self.viewport.x, y * self.scale + self.viewport.y)
+    }
+
+    /// The regions of a `width`x`height` window which the logical screen
+    /// doesn't cover, in pixels.
+    pub fn bars(&self, width: u32, height: u32) -> Vec<Rectangle> {
+        let (out_w, out_h) = (width as f64, height as f64);
+        let v = self.viewport;
+
+        vec![
+            Rectangle { x: 0.0, y: 0.0, w: v.x, h: out_h },
+            Rectangle { x: v.x + v.w, y: 0.0, w: out_w - v.x - v.w, h: out_h },
+            Rectangle { x: v.x, y: 0.0, w: v.w, h: v.y },
+            Rectangle { x: v.x, y: v.y + v.h, w: v.w, h: out_h - v.y - v.h },
+        ].into_iter()
+            .filter(|bar| bar.w >= 1.0 && bar.h >= 1.0)
+            .collect()
+    }
+}
+
+/// Describes the window created by `spawn`, and the renderer drawing in it.
+///
+/// # Examples
+///
+/// ```
+/// let config = WindowConfig::new("ArcadeRS Shooter")
+///     .size(1024, 768)
+///     .min_size(640, 480)
+///     .logical_size(320, 240)
+///     .integer_scale(true)
+///     .vsync(true);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    pub title: String,
+    pub size: (u32, u32),
+    pub min_size: Option<(u32, u32)>,
+    pub fullscreen: Fullscreen,
+
+    /// Whether the window should be shown without decorations
+    pub borderless: bool,
+    pub resizable: bool,
+    pub hidden: bool,
+
+    /// Whether the renderer should wait for the screen to refresh before
+    /// presenting a frame
+    pub vsync: bool,
+
+    /// Whether the renderer should use the GPU, instead of drawing in software
+    pub accelerated: bool,
+
+    /// The path of the image shown in the title bar and the task bar
+    pub icon: Option<String>,
+
+    /// The resolution in which the views draw, which is `size` unless told
+    /// otherwise. See `Screen`.
+    pub logical_size: Option<(u32, u32)>,
+
+    /// Whether the logical resolution is only scaled by whole numbers
+    pub integer_scale: bool,
+}
+
+impl WindowConfig {
+    /// Creates the configuration of a resizable, accelerated 800x600 window
+    /// named `title`.
+    pub fn new(title: &str) -> WindowConfig {
+        WindowConfig {
+            title: title.to_string(),
+            size: (800, 600),
+            min_size: None,
+            fullscreen: Fullscreen::Off,
+            borderless: false,
+            resizable: true,
+            hidden: false,
+            vsync: false,
+            accelerated: true,
+            icon: None,
+            logical_size: None,
+            integer_scale: false,
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> WindowConfig {
+        self.size = (width, height);
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> WindowConfig {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn fullscreen(mut self, fullscreen: Fullscreen) -> WindowConfig {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    pub fn borderless(mut self, borderless: bool) -> WindowConfig {
+        self.borderless = borderless;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> WindowConfig {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> WindowConfig {
+        self.hidden = hidden;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> WindowConfig {
+        self.vsync = vsync;
+        self
+    }
+
+    pub fn accelerated(mut self, accelerated: bool) -> WindowConfig {
+        self.accelerated = accelerated;
+        self
+    }
+
+    pub fn icon(mut self, path: &str) -> WindowConfig {
+        self.icon = Some(path.to_string());
+        self
+    }
+
+    pub fn logical_size(mut self, width: u32, height: u32) -> WindowConfig {
+        self.logical_size = Some((width, height));
+        self
+    }
+
+    pub fn integer_scale(mut self, integer_scale: bool) -> WindowConfig {
+        self.integer_scale = integer_scale;
+        self
+    }
+
+    /// Returns the logical screen described by `self`.
+    pub fn screen(&self) -> Screen {
+        let (width, height) = self.logical_size.unwrap_or(self.size);
+        Screen::new(width, height, self.integer_scale)
+    }
+
+    /// Creates the window described by `self`, along with its renderer.
+    pub fn build(&self, video: &VideoSubsystem) -> Result<Renderer<'static>, Error> {
+        let (width, height) = self.size;
+        let mut builder = video.window(&self.title, width, height);
+        builder.position_centered();
+
+        if self.accelerated {
+            builder.opengl();
+        }
+
+        if self.resizable {
+            builder.resizable();
+        }
+
+        if self.borderless {
+            builder.borderless();
+        }
+
+        if self.hidden {
+            builder.hidden();
+        }
+
+        match self.fullscreen {
+            Fullscreen::Off => {},
+            Fullscreen::Exclusive => { builder.fullscreen(); },
+            Fullscreen::Desktop => { builder.fullscreen_desktop(); },
+        }
+
+        let mut window = builder.build()
+            .map_err(|e| Error::Sdl(e.to_string()))?;
+
+        if let Some((min_w, min_h)) = self.min_size {
+            window.set_minimum_size(min_w, min_h);
+        }
+
+        if let Some(ref icon) = self.icon {
+            let path = Path::new(icon);
+
+            if !path.exists() {
+                return Err(Error::AssetNotFound(path.to_path_buf()));
+            }
+
+            let surface = Surface::from_file(path)
+                .map_err(|msg| Error::Decode(path.to_path_buf(), msg))?;
+
+            window.set_icon(surface);
+        }
+
+        // Transitions draw views into textures, so we always need those.
+        let mut renderer = window.renderer().target_texture();
+
+        renderer =
+            if self.accelerated { renderer.accelerated() }
+            else { renderer.software() };
+
+        if self.vsync {
+            renderer = renderer.present_vsync();
+        }
+
+        renderer.build()
+            .map_err(|e| Error::Sdl(e.to_string()))
+    }
+}
@@ -0,0 +1,104 @@
+use phi::data::Rectangle;
+use phi::ecs::Entity;
+use phi::gfx::{AnimatedSprite, DrawParams, Sprite};
+use phi::mask::Mask;
+use std::rc::Rc;
+
+/// The collision layers on which entities can be.
+pub mod layers {
+    pub const PLAYER: u32 = 1 << 0;
+    pub const ENEMY: u32 = 1 << 1;
+    pub const BULLET: u32 = 1 << 2;
+}
+
+/// Where an entity is, along with where it was before the last update, so
+/// that its motion can be interpolated.
+#[derive(Clone, Copy, Debug)]
+pub struct Position {
+    pub rect: Rectangle,
+    pub prev_rect: Rectangle,
+}
+
+impl Position {
+    pub fn new(rect: Rectangle) -> Position {
+        Position {
+            rect: rect,
+            prev_rect: rect,
+        }
+    }
+
+    /// Moves the entity to `rect` without any interpolation, as when it
+    /// respawns on the other side of the screen.
+    pub fn teleport(&mut self, rect: Rectangle) {
+        self.rect = rect;
+        self.prev_rect = rect;
+    }
+
+    /// Returns where the entity should be drawn, `alpha` of the way between
+    /// the last update and the next one.
+    pub fn lerp(&self, alpha: f64) -> Rectangle {
+        self.prev_rect.lerp(self.rect, alpha)
+    }
+}
+
+/// How many pixels the entity travels every second.
+#[derive(Clone, Copy, Debug)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// The sprite with which the entity is drawn.
+pub struct Appearance(pub Sprite);
+
+/// The animation with which the entity is drawn, instead of an `Appearance`.
+pub struct Animation(pub AnimatedSprite);
+
+/// How the sprite of the entity is rotated, flipped and colored.
+#[derive(Clone, Copy, Debug)]
+pub struct Transform(pub DrawParams);
+
+/// The number of degrees by which the `Transform` of the entity turns every
+/// second, clockwise.
+#[derive(Clone, Copy, Debug)]
+pub struct Spin(pub f64);
+
+/// Makes the entity part of collisions, through its position.
+#[derive(Clone, Debug)]
+pub struct Collider {
+    /// The layers on which the entity is, from `layers`
+    pub layer: u32,
+
+    /// The layers with which the entity collides
+    pub mask: u32,
+
+    /// The entities which the entity currently overlaps
+    pub contacts: Vec<Entity>,
+}
+
+impl Collider {
+    pub fn new(layer: u32, mask: u32) -> Collider {
+        Collider {
+            layer: layer,
+            mask: mask,
+            contacts: Vec::new(),
+        }
+    }
+}
+
+/// The solid pixels of the entity, for every frame of its `Animation`, or a
+/// single one if it isn't animated. Every entity with a `Collider` needs one,
+/// built once when the entity is created, since masks are costly to build.
+/// Use `Mask::solid` for entities which fill their whole rectangle.
+#[derive(Clone, Debug)]
+pub struct Shape(pub Rc<Vec<Mask>>);
+
+/// Marks the ship controlled by the player, holding every frame of its
+/// spritesheet.
+pub struct Player {
+    pub frames: Vec<Sprite>,
+}
+
+/// Marks an asteroid, which respawns when it leaves the screen.
+#[derive(Clone, Copy, Debug)]
+pub struct Asteroid;
@@ -1,20 +1,40 @@
-use phi::{Phi, View, ViewAction};
-use phi::data::Rectangle;
-use phi::gfx::{Sprite, AnimatedSprite, CopySprite};
+use phi::{Error, Phi, View, ViewAction};
+use phi::atlas::Atlas;
+use phi::collision::{Body, CollisionEvent, Collisions};
+use phi::data::{Rectangle, Vec2};
+use phi::ecs::{Entity, Storage, System, World};
+use phi::gfx::{AnimatedSprite, Camera, CopySprite, DrawParams};
+use phi::input::Action;
+use phi::mask::Mask;
+use phi::text::{Glyphs, TextStyle};
+use rand::Rng;
+use std::rc::Rc;
 use sdl2::pixels::Color;
-use sdl2::render::Renderer;
+use views::components::{layers, Animation, Appearance, Asteroid, Collider, Player, Position, Shape, Spin, Transform, Velocity};
 use views::shared::Background;
 
 /// Pixels traveled by the player's ship every second, when it's moving
 const PLAYER_SPEED: f64 = 180.0;
 
-const SHIP_W: f64 = 43.0;
-const SHIP_H: f64 = 39.0;
+/// The descriptors of the spritesheets
+const SHIP_ATLAS: &'static str = "assets/spaceship.json";
+const ASTEROID_ATLAS: &'static str = "assets/asteroid.json";
+
+/// The names of the frames of the ship in its atlas, in the order of
+/// `ShipFrame`
+const SHIP_FRAMES: [&'static str; 9] = [
+    "up-norm", "up-fast", "up-slow",
+    "mid-norm", "mid-fast", "mid-slow",
+    "down-norm", "down-fast", "down-slow",
+];
 
 const DEBUG: bool = false;
 
-/// The different states our ship can be in. In the image, they're ordered
-/// from left to right, top to bottom.
+/// The color by which the ship is tinted while it touches an enemy
+const HIT_COLOR: Color = Color::RGB(255, 80, 80);
+
+/// The different states our ship can be in, named in its atlas by
+/// `SHIP_FRAMES`.
 #[derive(Copy, Clone)]
 enum ShipFrame {
     UpNorm   = 0,
@@ -28,16 +48,21 @@ enum ShipFrame {
     DownSlow = 8,
 }
 
-struct Ship {
-    rect: Rectangle,
-    sprites: Vec<Sprite>,
-    current: ShipFrame,
-}
-
 pub struct ShipView {
-    player: Ship,
+    /// The player's ship, the asteroids, and whatever else moves around
+    world: World,
+
+    /// Update the world, in order, on every tick
+    systems: Vec<Box<System>>,
+
+    /// Shows the world, which is exactly as large as the screen for now
+    camera: Camera,
+
+    /// The time, in seconds, since the game started
+    elapsed: f64,
 
-    asteroid: Asteroid,
+    /// The digits in which the time is shown
+    digits: Glyphs,
 
     bg_back: Background,
     bg_middle: Background,
@@ -45,89 +70,186 @@ pub struct ShipView {
 }
 
 impl ShipView {
-    pub fn new(phi: &mut Phi) -> ShipView {
-        let spritesheet = Sprite::load(&phi.renderer, "assets/spaceship.png").unwrap();
-
-        let mut sprites = Vec::with_capacity(9);
-
-        for y in 0..3 {
-            for x in 0..3 {
-                sprites.push(spritesheet.region(Rectangle {
-                    x: SHIP_W * x as f64,
-                    y: SHIP_H * y as f64,
-                    w: SHIP_W,
-                    h: SHIP_H,
-                }).unwrap());
-            }
-        }
-
-        ShipView {
-            player: Ship {
-                rect: Rectangle {
-                    x: 64.0,
-                    y: 64.0,
-                    w: SHIP_W,
-                    h: SHIP_H,
-                },
+    pub fn new(phi: &mut Phi) -> Result<ShipView, Error> {
+        let ship_atlas = Atlas::load(SHIP_ATLAS)?;
+        let frames = SHIP_FRAMES.iter()
+            .map(|name| ship_atlas.sprite(phi, name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (ship_w, ship_h) = frames[ShipFrame::MidNorm as usize].size();
+
+        let asteroid_atlas = Atlas::load(ASTEROID_ATLAS)?;
+        let asteroid_sprites = asteroid_atlas.sprites(phi, "spin")?;
+        let (asteroid_w, asteroid_h) = asteroid_sprites[0].size();
+
+        let mut world = World::new();
+
+        let ship = world.create();
+        world.insert(ship, Position::new(Rectangle {
+            x: 64.0,
+            y: 64.0,
+            w: ship_w,
+            h: ship_h,
+        }));
+        world.insert(ship, Velocity { x: 0.0, y: 0.0 });
+        world.insert(ship, Appearance(frames[ShipFrame::MidNorm as usize].clone()));
+        world.insert(ship, Collider::new(layers::PLAYER, layers::ENEMY));
+        // Every frame of the ship has about the same outline, so the one
+        // flying straight is used for all of them.
+        let ship_mask = Mask::load(ship_atlas.image(), &[ship_atlas.region(SHIP_FRAMES[ShipFrame::MidNorm as usize])?])?;
+        world.insert(ship, Shape(Rc::new(ship_mask)));
+        world.insert(ship, Player { frames: frames });
+
+        let asteroid = world.create();
+        world.insert(asteroid, Position::new(Rectangle {
+            x: 128.0,
+            y: 128.0,
+            w: asteroid_w,
+            h: asteroid_h,
+        }));
+        world.insert(asteroid, Velocity { x: 0.0, y: 0.0 });
+        world.insert(asteroid, Animation(AnimatedSprite::with_fps(asteroid_sprites, 15.0)));
+        world.insert(asteroid, Transform(DrawParams::new()));
+        world.insert(asteroid, Spin(0.0));
+        world.insert(asteroid, Collider::new(layers::ENEMY, layers::PLAYER | layers::BULLET));
+        let asteroid_masks = Mask::load(asteroid_atlas.image(), &asteroid_atlas.regions("spin")?)?;
+        world.insert(asteroid, Shape(Rc::new(asteroid_masks)));
+        world.insert(asteroid, Asteroid);
+        respawn_asteroid(&world, asteroid, phi);
 
-                sprites: sprites,
-                current: ShipFrame::MidNorm,
-            },
-
-            asteroid: Asteroid::new(phi),
-
-            bg_back: Background {
-                pos: 0.0,
-                vel: 20.0,
-                sprite: Sprite::load(&phi.renderer, "assets/starBG.png").unwrap(),
-            },
-
-            bg_middle: Background {
-                pos: 0.0,
-                vel: 40.0,
-                sprite: Sprite::load(&phi.renderer, "assets/starMG.png").unwrap(),
-            },
-
-            bg_front: Background {
-                pos: 0.0,
-                vel: 80.0,
-                sprite: Sprite::load(&phi.renderer, "assets/starFG.png").unwrap(),
-            },
-        }
+        let (w, h) = phi.output_size();
+        let camera = Camera::new(w, h)
+            .with_bounds(Rectangle { x: 0.0, y: 0.0, w: w, h: h });
+
+        Ok(ShipView {
+            world: world,
+
+            systems: vec![
+                Box::new(PlayerControl) as Box<System>,
+                Box::new(Movement),
+                Box::new(Animate),
+                Box::new(Rotate),
+                Box::new(Boundaries),
+                Box::new(Collision { collisions: Collisions::new() }),
+            ],
+
+            camera: camera,
+            elapsed: 0.0,
+            digits: Glyphs::new(phi, TextStyle::new("assets/belligerent.ttf", 24, Color::RGB(255, 255, 255)), "0123456789.")?,
+
+            bg_back: Background::new(phi.load_sprite("assets/starBG.png")?, 20.0),
+            bg_middle: Background::new(phi.load_sprite("assets/starMG.png")?, 40.0),
+            bg_front: Background::new(phi.load_sprite("assets/starFG.png")?, 80.0),
+        })
     }
 }
 
 impl View for ShipView {
-    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
-        if phi.events.now.quit || phi.events.now.key_escape == Some(true) {
+    fn update(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
             return ViewAction::Quit;
         }
 
-        // let traveled = PLAYER_SPEED * elapsed;
-        let diagonal =
-            (phi.events.key_up ^ phi.events.key_down) &&
-            (phi.events.key_left ^ phi.events.key_right);
+        if phi.action_pressed(Action::Pause) {
+            return ::views::pause::PauseView::new(phi)
+                .map(|view| ViewAction::PushView(Box::new(view)))
+                .unwrap_or_else(ViewAction::Fail);
+        }
+
+        for system in &mut self.systems {
+            system.run(&mut self.world, phi, elapsed);
+        }
 
-        let moved =
-            if diagonal { 1.0/2.0f64.sqrt() }
-            else { 1.0 } * PLAYER_SPEED * elapsed;
+        self.elapsed += elapsed;
 
-        let dx = match (phi.events.key_left, phi.events.key_right) {
-            (true, true) | (false, false) => 0.0,
-            (true, false) => -moved,
-            (false, true) => moved,
-        };
+        // Update the backgrounds
+        self.bg_back.update(elapsed);
+        self.bg_middle.update(elapsed);
+        self.bg_front.update(elapsed);
 
-        let dy = match (phi.events.key_up, phi.events.key_down) {
-            (true, true) | (false, false) => 0.0,
-            (true, false) => -moved,
-            (false, true) => moved,
-        };
+        ViewAction::None
+    }
+
+    fn render(&self, phi: &mut Phi, alpha: f64) {
+        // The time until the next update. The world is drawn between its last
+        // two updates, so it is this far behind the latest one.
+        let ahead = (1.0 - alpha) * phi.tick_duration();
 
-        self.player.rect.x += dx;
-        self.player.rect.y += dy;
+        // Clear the screen
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
 
-        self.player.current =
+        // Render the Background
+        self.bg_back.render(phi, alpha);
+        self.bg_middle.render(phi, alpha);
+
+        // Render the entities, between their last two positions
+        let positions = self.world.read::<Position>();
+        let appearances = self.world.read::<Appearance>();
+        let animations = self.world.read::<Animation>();
+        let colliders = self.world.read::<Collider>();
+        let transforms = self.world.read::<Transform>();
+        let spins = self.world.read::<Spin>();
+        let players = self.world.read::<Player>();
+
+        for (entity, pos) in positions.iter() {
+            let rect = pos.lerp(alpha);
+
+            let mut params = transforms.get(entity).map_or(DrawParams::new(), |t| t.0);
+            params.angle -= spins.get(entity).map_or(0.0, |spin| spin.0 * ahead);
+
+            let hit = colliders.get(entity).map_or(false, |c| !c.contacts.is_empty());
+            if hit && players.contains(entity) {
+                params.color = HIT_COLOR;
+            }
+
+            // Render the bounding box (for debugging)
+            if DEBUG && colliders.contains(entity) {
+                phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
+                if let Ok(rect) = self.camera.rect_to_screen(rect).to_sdl() {
+                    phi.renderer.fill_rect(rect);
+                }
+            }
+
+            if let Some(&Appearance(ref sprite)) = appearances.get(entity) {
+                phi.renderer.copy_sprite_world_ex(sprite, &self.camera, rect, &params);
+            } else if let Some(&Animation(ref sprite)) = animations.get(entity) {
+                phi.renderer.copy_sprite_world_ex(sprite, &self.camera, rect, &params);
+            }
+        }
+
+        // Render the front Background
+        self.bg_front.render(phi, alpha);
+
+        // Render the time since the game started, in screen coordinates. This
+        // changes on most frames, so it is drawn from the sprites of its
+        // digits rather than rendering a new texture every time.
+        let time = format!("{:.1}", self.elapsed - ahead);
+        self.digits.render(phi, &time, 10.0, 10.0);
+    }
+}
+
+
+/// Steers the player's ship according to the actions which are held, and
+/// shows the frame matching its heading.
+struct PlayerControl;
+
+impl System for PlayerControl {
+    fn run(&mut self, world: &mut World, phi: &mut Phi, _: f64) {
+        let up = phi.action_held(Action::MoveUp);
+        let down = phi.action_held(Action::MoveDown);
+        let left = phi.action_held(Action::MoveLeft);
+        let right = phi.action_held(Action::MoveRight);
+
+        // Opposite directions cancel out, and moving diagonally is no faster
+        // than moving straight.
+        let direction = Vec2::new(
+            (right as i32 - left as i32) as f64,
+            (down as i32 - up as i32) as f64);
+
+        let vel = direction.normalize() * PLAYER_SPEED;
+        let (dx, dy) = (vel.x, vel.y);
+
+        let frame =
             if dy < 0.0 {
                 if dx < 0.0 { ShipFrame::UpSlow }
                 else if dx == 0.0 { ShipFrame::UpNorm }
@@ -142,134 +264,192 @@ impl View for ShipView {
                 else { ShipFrame::DownFast }
             };
 
-        // The movable region spans the entire window height, and 70%
-        // of the window's width.
-        let (screen_w, screen_h) = phi.output_size();
-        let movable_region = Rectangle {
-            x: 0.0,
-            y: 0.0,
-            w: screen_w * 0.7,
-            h: screen_h,
-        };
-
-        self.player.rect = self.player.rect.move_inside(movable_region).unwrap();
+        let players = world.read::<Player>();
+        let mut velocities = world.write::<Velocity>();
+        let mut appearances = world.write::<Appearance>();
 
+        for (entity, player) in players.iter() {
+            velocities.insert(entity, Velocity { x: dx, y: dy });
+            appearances.insert(entity, Appearance(player.frames[frame as usize].clone()));
+        }
+    }
+}
 
-        // Update the asteroid
-        self.asteroid.update(phi, elapsed);
+/// Moves every entity according to its velocity.
+struct Movement;
 
-        // Clear the screen
-        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
-        phi.renderer.clear();
+impl System for Movement {
+    fn run(&mut self, world: &mut World, _: &mut Phi, elapsed: f64) {
+        let velocities = world.read::<Velocity>();
 
-        // Render the Background
-        self.bg_back.render(&mut phi.renderer, elapsed);
-        self.bg_middle.render(&mut phi.renderer, elapsed);
+        for (entity, pos) in world.write::<Position>().iter_mut() {
+            pos.prev_rect = pos.rect;
 
-        // Render the bounding box (for debugging)
-        if DEBUG {
-            phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-            phi.renderer.fill_rect(self.player.rect.to_sdl().unwrap());
+            if let Some(vel) = velocities.get(entity) {
+                pos.rect.x += vel.x * elapsed;
+                pos.rect.y += vel.y * elapsed;
+            }
         }
+    }
+}
 
-        // Render the ship texture
-        phi.renderer.copy_sprite(
-            &self.player.sprites[self.player.current as usize],
-            self.player.rect,
-        );
-
-        // Render the asteroid
-        self.asteroid.render(phi);
-
-        // Render the front Background
-        self.bg_middle.render(&mut phi.renderer, elapsed);
+/// Plays every animation.
+struct Animate;
 
-        ViewAction::None
+impl System for Animate {
+    fn run(&mut self, world: &mut World, _: &mut Phi, elapsed: f64) {
+        for (_, animation) in world.write::<Animation>().iter_mut() {
+            animation.0.add_time(elapsed);
+        }
     }
 }
 
+/// Turns the entities which spin.
+struct Rotate;
 
-const ASTEROID_PATH: &'static str = "assets/asteroid.png";
-const ASTEROIDS_WIDE: usize = 21;
-const ASTEROIDS_HIGH: usize = 7;
-const ASTEROIDS_TOTAL: usize = ASTEROIDS_WIDE * ASTEROIDS_HIGH - 4;
-const ASTEROIDS_SIDE: f64 = 96.0;
+impl System for Rotate {
+    fn run(&mut self, world: &mut World, _: &mut Phi, elapsed: f64) {
+        let spins = world.read::<Spin>();
 
-struct Asteroid {
-    sprite: AnimatedSprite,
-    rect: Rectangle,
-    vel: f64,
+        for (entity, transform) in world.write::<Transform>().iter_mut() {
+            if let Some(spin) = spins.get(entity) {
+                transform.0.angle = (transform.0.angle + spin.0 * elapsed) % 360.0;
+            }
+        }
+    }
 }
 
-impl Asteroid {
-    fn new(phi: &mut Phi) -> Asteroid {
-        let mut asteroid = Asteroid {
-            sprite: Asteroid::get_sprite(phi, 15.0),
-            rect: Rectangle {
-                x: 128.0,
-                y: 128.0,
-                w: ASTEROIDS_SIDE,
-                h: ASTEROIDS_SIDE,
-            },
-            vel: 0.0,
+/// Keeps the player's ship inside of the screen, and respawns the asteroids
+/// which left it.
+struct Boundaries;
+
+impl System for Boundaries {
+    fn run(&mut self, world: &mut World, phi: &mut Phi, _: f64) {
+        // The movable region spans the entire window height, and 70%
+        // of the window's width.
+        let (screen_w, screen_h) = phi.output_size();
+        let movable_region = Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: screen_w * 0.7,
+            h: screen_h,
         };
 
-        asteroid.reset(phi);
-        asteroid
-    }
+        for entity in world.query2::<Player, Position>() {
+            let mut positions = world.write::<Position>();
+            if let Some(pos) = positions.get_mut(entity) {
+                pos.rect = pos.rect.move_inside(movable_region).unwrap();
+            }
+        }
 
-    fn update(&mut self, phi: &mut Phi, dt: f64) {
-        self.rect.x -= dt * self.vel;
-        self.sprite.add_time(dt);
+        for entity in world.query2::<Asteroid, Position>() {
+            let gone = world.read::<Position>().get(entity)
+                .map_or(false, |pos| pos.rect.x <= -pos.rect.w);
 
-        if self.rect.x <= -ASTEROIDS_SIDE {
-            self.reset(phi);
+            if gone {
+                respawn_asteroid(world, entity, phi);
+            }
         }
     }
+}
 
-    fn render(&self, phi: &mut Phi) {
-        phi.renderer.copy_sprite(&self.sprite, self.rect);
-    }
 
-    fn reset(&mut self, phi: &mut Phi) {
-        let (w, h) = phi.output_size();
+/// Keeps track of the entities which overlap, in the `contacts` of their
+/// collider. Entities only overlap where the pixels of their `Shape` do.
+struct Collision {
+    collisions: Collisions<Entity>,
+}
 
-        // FPS between 10.0 and 30.0
-        self.sprite.set_fps(::rand::random::<f64>().abs() * 20.0 + 10.0);
+impl System for Collision {
+    fn run(&mut self, world: &mut World, _: &mut Phi, _: f64) {
+        let positions = world.read::<Position>();
+        let mut colliders = world.write::<Collider>();
+
+        self.collisions.clear();
+
+        for (entity, collider) in colliders.iter() {
+            if let Some(pos) = positions.get(entity) {
+                self.collisions.insert(Body {
+                    id: entity,
+                    rect: pos.rect,
+                    layer: collider.layer,
+                    mask: collider.mask,
+                });
+            }
+        }
 
-        self.rect = Rectangle {
-            x: w,
-            y: ::rand::random::<f64>().abs() * (h - ASTEROIDS_SIDE),
-            w: ASTEROIDS_SIDE,
-            h: ASTEROIDS_SIDE,
-        };
+        let shapes = world.read::<Shape>();
+        let animations = world.read::<Animation>();
 
-        // vel between 50.0 and 150.0
-        self.vel = ::rand::random::<f64>().abs() * 100.0 + 50.0;
-    }
+        let events = self.collisions.detect_with(|a, b| {
+            let pos_a = (a.rect.x, a.rect.y);
+            let pos_b = (b.rect.x, b.rect.y);
 
-    fn get_sprite(phi: &mut Phi, fps: f64) -> AnimatedSprite {
-        let asteroid_spritesheet = Sprite::load(&mut phi.renderer, ASTEROID_PATH).unwrap();
-        let mut asteroid_sprites = Vec::with_capacity(ASTEROIDS_TOTAL);
+            // Every collider is given a shape when it's created, so this
+            // only falls back to the rectangles if one was forgotten.
+            match (frame_mask(&shapes, &animations, a.id), frame_mask(&shapes, &animations, b.id)) {
+                (Some(mask_a), Some(mask_b)) => mask_a.overlaps(pos_a, mask_b, pos_b),
+                _ => true,
+            }
+        });
 
-        for yth in 0..ASTEROIDS_HIGH {
-            for xth in 0..ASTEROIDS_WIDE {
-                // There are four asteroids missing at the end of the sprite. We don't want those.
-                if ASTEROIDS_WIDE * yth + xth >= ASTEROIDS_TOTAL {
-                    break;
-                }
+        for event in events {
+            match event {
+                CollisionEvent::Enter(a, b) => {
+                    if let Some(collider) = colliders.get_mut(a) { collider.contacts.push(b); }
+                    if let Some(collider) = colliders.get_mut(b) { collider.contacts.push(a); }
+                },
 
-                asteroid_sprites.push(
-                    asteroid_spritesheet.region(Rectangle {
-                        x: xth as f64 * ASTEROIDS_SIDE,
-                        y: yth as f64 * ASTEROIDS_SIDE,
-                        w: ASTEROIDS_SIDE,
-                        h: ASTEROIDS_SIDE,
-                    }).unwrap()
-                );
+                CollisionEvent::Exit(a, b) => {
+                    if let Some(collider) = colliders.get_mut(a) { collider.contacts.retain(|&e| e != b); }
+                    if let Some(collider) = colliders.get_mut(b) { collider.contacts.retain(|&e| e != a); }
+                },
+
+                CollisionEvent::Stay(..) => {},
             }
         }
+    }
+}
 
-        AnimatedSprite::with_fps(asteroid_sprites, fps)
+/// Returns the mask of the frame which `entity` currently shows, if it has a
+/// `Shape`.
+fn frame_mask<'a>(shapes: &'a Storage<Shape>, animations: &Storage<Animation>, entity: Entity) -> Option<&'a Mask> {
+    shapes.get(entity).map(|&Shape(ref masks)| {
+        let frame = animations.get(entity).map_or(0, |animation| animation.0.current_frame());
+        &masks[frame % masks.len()]
+    })
+}
+
+/// Sends `asteroid` back to the right of the screen, at a random height and
+/// speed.
+fn respawn_asteroid(world: &World, asteroid: Entity, phi: &mut Phi) {
+    let (w, h) = phi.output_size();
+
+    // FPS between 10.0 and 30.0. The current frame picks the mask used for
+    // collisions, so this affects gameplay and is drawn from the main stream.
+    let fps = phi.rng.gen::<f64>() * 20.0 + 10.0;
+    if let Some(animation) = world.write::<Animation>().get_mut(asteroid) {
+        animation.0.set_fps(fps);
     }
-}
\ No newline at end of file
+
+    // Spin between -90 and 90 degrees per second. Only the sprite turns: the
+    // asteroid is round enough for its mask to stay as it is.
+    let spin = phi.rng.stream("cosmetic").gen::<f64>() * 180.0 - 90.0;
+    if let Some(current) = world.write::<Spin>().get_mut(asteroid) {
+        current.0 = spin;
+    }
+
+    // The asteroid is teleported, so there is nothing to interpolate
+    let height = phi.rng.gen::<f64>();
+    if let Some(pos) = world.write::<Position>().get_mut(asteroid) {
+        pos.teleport(Rectangle {
+            x: w,
+            y: height * (h - pos.rect.h),
+            ..pos.rect
+        });
+    }
+
+    // vel between 50.0 and 150.0
+    let vel = phi.rng.gen::<f64>() * 100.0 + 50.0;
+    world.write::<Velocity>().insert(asteroid, Velocity { x: -vel, y: 0.0 });
+}
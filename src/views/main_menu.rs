@@ -1,9 +1,22 @@
-use phi::{Phi, View, ViewAction};
+use phi::{Error, Phi, View, ViewAction};
+use phi::input;
+use phi::transition::Transition;
 use phi::gfx::{Sprite, CopySprite};
-use phi::data::Rectangle;
+use phi::data::{Rectangle, Vec2};
+use phi::text::Align;
 use views::shared::Background;
+use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 
+/// The height of every label in the menu, in pixels
+const LABEL_H: f64 = 50.0;
+
+/// The width of the menu's box, in pixels, before it is animated
+const BOX_W: f64 = 360.0;
+
+/// Played whenever another label is selected
+const SELECT_SOUND: &'static str = "assets/select.wav";
+
 pub struct MainMenuView {
     actions: Vec<Action>,
     selected: i8,
@@ -15,93 +28,148 @@ pub struct MainMenuView {
 }
 
 impl MainMenuView {
-    pub fn new(phi: &mut Phi) -> MainMenuView {
-        MainMenuView {
+    pub fn new(phi: &mut Phi) -> Result<MainMenuView, Error> {
+        phi.audio.load_sound(SELECT_SOUND)?;
+
+        Ok(MainMenuView {
             actions: vec![
                 Action::new(phi, "New Game", Box::new(|phi| {
-                    ViewAction::ChangeView(Box::new(::views::game::ShipView::new(phi)))
-                })),
+                    ::views::game::ShipView::new(phi)
+                        .map(|view| ViewAction::ChangeViewWith(Box::new(view), Transition::Crossfade(0.6)))
+                        .unwrap_or_else(ViewAction::Fail)
+                }))?,
 
                 Action::new(phi, "Quit", Box::new(|phi| {
                     ViewAction::Quit
-                })),
+                }))?,
             ],
 
             // Start with the first option selected
             selected: 0,
             elapsed: 0.0,
 
-            bg_back: Background {
-                pos: 0.0,
-                vel: 20.0,
-                sprite: Sprite::load(&mut phi.renderer, "assets/starBG.png").unwrap(),
-            },
-
-            bg_middle: Background {
-                pos: 0.0,
-                vel: 40.0,
-                sprite: Sprite::load(&mut phi.renderer, "assets/starMG.png").unwrap(),
-            },
-
-            bg_front: Background {
-                pos: 0.0,
-                vel: 80.0,
-                sprite: Sprite::load(&mut phi.renderer, "assets/starFG.png").unwrap(),
-            },
+            bg_back: Background::new(phi.load_sprite("assets/starBG.png")?, 20.0),
+            bg_middle: Background::new(phi.load_sprite("assets/starMG.png")?, 40.0),
+            bg_front: Background::new(phi.load_sprite("assets/starFG.png")?, 80.0),
+        })
+    }
+
+    /// The region of the screen in which the `i`th label is shown, which the
+    /// cursor must hover to select it.
+    fn label_region(&self, phi: &Phi, i: usize) -> Rectangle {
+        let (win_w, win_h) = phi.output_size();
+        let box_h = self.actions.len() as f64 * LABEL_H;
+
+        Rectangle {
+            x: (win_w - BOX_W) / 2.0,
+            y: (win_h - box_h) / 2.0 + LABEL_H * i as f64,
+            w: BOX_W,
+            h: LABEL_H,
         }
     }
+
+    /// The index of the label under the point `(x, y)`, if any.
+    fn label_at(&self, phi: &Phi, (x, y): (f64, f64)) -> Option<usize> {
+        (0..self.actions.len()).find(|&i| self.label_region(phi, i).contains_point(Vec2::new(x, y)))
+    }
 }
 
 impl View for MainMenuView {
-    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
-        if phi.events.now.quit || phi.events.now.key_escape == Some(true) {
+    fn update(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit || phi.action_pressed(input::Action::Back) {
             return ViewAction::Quit;
         }
 
-        if phi.events.now.key_space == Some(true) || phi.events.now.key_enter == Some(true) {
+        if phi.action_pressed(input::Action::Confirm) {
             return (self.actions[self.selected as usize].func)(phi);
         }
 
-        if phi.events.now.key_up == Some(true) {
+        let previous = self.selected;
+
+        // The label under the cursor is selected whenever the mouse moves, so
+        // that it doesn't steal the selection from the keyboard otherwise.
+        if phi.events.now.mouse_moved {
+            if let Some(i) = self.label_at(phi, phi.events.mouse) {
+                self.selected = i as i8;
+            }
+        }
+
+        let clicked = phi.events.now.clicks.iter()
+            .filter(|&&(button, _)| button == MouseButton::Left)
+            .filter_map(|&(_, pos)| self.label_at(phi, pos))
+            .next();
+
+        if let Some(i) = clicked {
+            self.selected = i as i8;
+            return (self.actions[i].func)(phi);
+        }
+
+        if phi.action_pressed(input::Action::MoveUp) {
             self.selected -= 1;
             if self.selected < 0 {
                 self.selected = self.actions.len() as i8 - 1;
             }
         }
 
-        if phi.events.now.key_down == Some(true) {
+        if phi.action_pressed(input::Action::MoveDown) {
             self.selected += 1;
             if self.selected >= self.actions.len() as i8 {
                 self.selected = 0;
             }
         }
 
+        if self.selected != previous {
+            if let Err(error) = phi.audio.play_sound(SELECT_SOUND) {
+                return ViewAction::Fail(error);
+            }
+        }
+
+        // Update the backgrounds
+        self.bg_back.update(elapsed);
+        self.bg_middle.update(elapsed);
+        self.bg_front.update(elapsed);
+
+        // Used to animate the menu's box
+        self.elapsed += elapsed * 4.0;
+
+        ViewAction::None
+    }
+
+    fn render(&self, phi: &mut Phi, alpha: f64) {
+        // The time left until the next update, during which the menu is
+        // drawn between its last two states
+        let ahead = (1.0 - alpha) * phi.tick_duration();
+
         // Clear the screen
         phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
         phi.renderer.clear();
 
         // Render the backgrounds
-        self.bg_back.render(&mut phi.renderer, elapsed);
-        self.bg_middle.render(&mut phi.renderer, elapsed);
-        self.bg_front.render(&mut phi.renderer, elapsed);
+        self.bg_back.render(phi, alpha);
+        self.bg_middle.render(phi, alpha);
+        self.bg_front.render(phi, alpha);
 
         // Definitions for the menu's layout
-        self.elapsed += elapsed * 4.0;
+        let elapsed = self.elapsed - ahead * 4.0;
         let (win_w, win_h) = phi.output_size();
-        let label_h = 50.0;
+        let label_h = LABEL_H;
         let border_width = 3.0;
-        let box_w = 360.0 + 5.0 * self.elapsed.sin();
+        let box_w = BOX_W + 5.0 * elapsed.sin();
         let box_h = self.actions.len() as f64 * label_h;
-        let margin_h = 10.0 + 5.0 * (self.elapsed + 1.0).sin();
+        let margin_h = 10.0 + 5.0 * (elapsed + 1.0).sin();
 
         // Render the border of the colored box containing the labels
         phi.renderer.set_draw_color(Color::RGB(70, 15, 70));
-        phi.renderer.fill_rect(Rectangle {
+        let border = Rectangle {
             w: box_w + border_width * 2.0,
             h: box_h + border_width * 2.0 + margin_h * 2.0,
             x: (win_w - box_w) / 2.0 - border_width,
             y: (win_h - box_h) / 2.0 - margin_h - border_width,
-        }.to_sdl().unwrap());
+        };
+
+        if let Ok(rect) = border.to_sdl() {
+            phi.renderer.fill_rect(rect);
+        }
 
         // Render the labels in the menu
         for (i, action) in self.actions.iter().enumerate() {
@@ -110,7 +178,7 @@ impl View for MainMenuView {
                 phi.renderer.copy_sprite(&action.hover_sprite, Rectangle {
                     w: w,
                     h: h,
-                    x: (win_w - w) / 2.0,
+                    x: Align::Center.offset(w, win_w),
                     y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
                 });
             } else {
@@ -118,13 +186,11 @@ impl View for MainMenuView {
                 phi.renderer.copy_sprite(&action.idle_sprite, Rectangle {
                     w: w,
                     h: h,
-                    x: (win_w - w) / 2.0,
+                    x: Align::Center.offset(w, win_w),
                     y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
                 });
             }
         }
-
-        ViewAction::None
     }
 }
 
@@ -140,12 +206,12 @@ struct Action {
 }
 
 impl Action {
-    fn new(phi: &mut Phi, label: &'static str, func: Box<Fn(&mut Phi) -> ViewAction>) -> Action {
-        Action {
+    fn new(phi: &mut Phi, label: &'static str, func: Box<Fn(&mut Phi) -> ViewAction>) -> Result<Action, Error> {
+        Ok(Action {
             func: func,
-            idle_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 32, Color::RGB(220, 220, 220)).unwrap(),
-            hover_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 38, Color::RGB(255, 255, 255)).unwrap(),
-        }
+            idle_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 32, Color::RGB(220, 220, 220))?,
+            hover_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 38, Color::RGB(255, 255, 255))?,
+        })
     }
 }
 
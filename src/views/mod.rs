@@ -1,3 +1,5 @@
 pub mod shared;
+pub mod components;
 pub mod game;
 pub mod main_menu;
+pub mod pause;
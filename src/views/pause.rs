@@ -0,0 +1,84 @@
+use phi::{Error, Phi, View, ViewAction};
+use phi::input::Action;
+use phi::transition::Transition;
+use phi::gfx::{Sprite, CopySprite};
+use phi::data::Rectangle;
+use phi::text::{Align, TextBlock, TextLayout, TextStyle};
+use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+
+/// Shown over a frozen game when the player presses escape.
+pub struct PauseView {
+    title: Sprite,
+    hint: TextBlock,
+}
+
+impl PauseView {
+    pub fn new(phi: &mut Phi) -> Result<PauseView, Error> {
+        let hint = format!("{}: resume\n{}: main menu",
+            phi.bindings.label(Action::Pause),
+            phi.bindings.label(Action::Confirm));
+
+        Ok(PauseView {
+            title: phi.ttf_str_sprite("Paused", "assets/belligerent.ttf", 48, Color::RGB(255, 255, 255))?,
+            hint: TextLayout::new(TextStyle::new("assets/belligerent.ttf", 20, Color::RGB(220, 220, 220)))
+                .align(Align::Center)
+                .line_spacing(1.2)
+                .render(phi, &hint)?,
+        })
+    }
+}
+
+impl View for PauseView {
+    fn update(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        if phi.action_pressed(Action::Pause) || phi.action_pressed(Action::Back) {
+            return ViewAction::PopView;
+        }
+
+        if phi.action_pressed(Action::Confirm) {
+            return ::views::main_menu::MainMenuView::new(phi)
+                .map(|view| ViewAction::ChangeViewWith(Box::new(view), Transition::FadeToBlack(0.8)))
+                .unwrap_or_else(ViewAction::Fail);
+        }
+
+        ViewAction::None
+    }
+
+    fn render(&self, phi: &mut Phi, _: f64) {
+        let (win_w, win_h) = phi.output_size();
+
+        // Darken the game below
+        phi.renderer.set_blend_mode(BlendMode::Blend);
+        phi.renderer.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        if let Ok(rect) = (Rectangle { x: 0.0, y: 0.0, w: win_w, h: win_h }).to_sdl() {
+            phi.renderer.fill_rect(rect);
+        }
+        phi.renderer.set_blend_mode(BlendMode::None);
+
+        // Render the labels, centered on the screen
+        let (title_w, title_h) = self.title.size();
+        let (hint_w, hint_h) = self.hint.size();
+
+        phi.renderer.copy_sprite(&self.title, Rectangle {
+            w: title_w,
+            h: title_h,
+            x: Align::Center.offset(title_w, win_w),
+            y: (win_h - title_h - hint_h) / 2.0,
+        });
+
+        phi.renderer.copy_sprite(&self.hint, Rectangle {
+            w: hint_w,
+            h: hint_h,
+            x: Align::Center.offset(hint_w, win_w),
+            y: (win_h + title_h - hint_h) / 2.0,
+        });
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -1,38 +1,64 @@
+use phi::Phi;
 use phi::data::Rectangle;
 use phi::gfx::{Sprite, CopySprite};
-use sdl2::render::Renderer;
 
 #[derive(Clone)]
 pub struct Background {
     pub pos: f64,
+    /// The position before the last update, so that motion can be interpolated
+    pub prev_pos: f64,
     /// The amount of pixels moved to the left every second
     pub vel: f64,
     pub sprite: Sprite,
 }
 
 impl Background {
-    pub fn render(&mut self, renderer: &mut Renderer, elapsed: f64) {
+    pub fn new(sprite: Sprite, vel: f64) -> Background {
+        Background {
+            pos: 0.0,
+            prev_pos: 0.0,
+            vel: vel,
+            sprite: sprite,
+        }
+    }
+
+    pub fn update(&mut self, elapsed: f64) {
         // We define the logical position as depending solely on the time and
         // the dimensions of the image, not on the screen's size
         let size = self.sprite.size();
+        self.prev_pos = self.pos;
         self.pos += self.vel * elapsed;
+
+        // Both positions wrap together, so that the motion between them stays
+        // the same.
         if self.pos > size.0 {
             self.pos -= size.0;
+            self.prev_pos -= size.0;
         }
+    }
 
+    /// Renders the background `alpha` of the way between the last update and
+    /// the next one.
+    pub fn render(&self, phi: &mut Phi, alpha: f64) {
         // We determine the scale ratio of the image to the sprite
-        let (win_w, win_h) = renderer.output_size().unwrap();
-        let scale = win_h as f64 / size.1;
+        let size = self.sprite.size();
+        let mut pos = (self.prev_pos + (self.pos - self.prev_pos) * alpha) % size.0;
+        if pos < 0.0 {
+            pos += size.0;
+        }
+
+        let (win_w, win_h) = phi.output_size();
+        let scale = win_h / size.1;
 
-        let mut physical_left = -self.pos * scale;
+        let mut physical_left = -pos * scale;
 
-        while physical_left < win_w as f64 {
+        while physical_left < win_w {
             // while the left of the image is still inside the window...
-            renderer.copy_sprite(&self.sprite, Rectangle {
+            phi.renderer.copy_sprite(&self.sprite, Rectangle {
                 x: physical_left,
                 y: 0.0,
                 w: size.0 * scale,
-                h: win_h as f64,
+                h: win_h,
             });
 
             physical_left += size.0 * scale;